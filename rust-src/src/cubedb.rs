@@ -0,0 +1,94 @@
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::{Algorithm, Solution, StepInfo};
+
+const KINDS: [&str; 7] = ["eo", "dr", "htr", "fr", "slice", "finish", "insertions"];
+
+// Inverse of StepInfo::label(). HTR takes its variant from the preceding DR.
+fn parse_label(label: &str, previous: Option<&StepInfo>) -> PyResult<StepInfo> {
+    let label = label.trim().to_lowercase();
+    let kind = KINDS
+        .iter()
+        .find(|k| label.starts_with(*k))
+        .ok_or_else(|| PyValueError::new_err(format!("Unknown step: {}", label)))?;
+    let variant = match (&label[kind.len()..], *kind) {
+        ("", "htr") => previous
+            .filter(|p| p.kind == "dr")
+            .map(|p| p.variant.clone())
+            .unwrap_or_else(|| "ud".to_string()),
+        ("lr", _) => "rl".to_string(),
+        (v, _) => v.to_string(),
+    };
+    StepInfo::new(kind, &variant)
+}
+
+/// Formats a solution as a cubedb reconstruction: the scramble, a blank line, then
+/// one `moves // step` line per step.
+#[pyfunction]
+pub fn to_cubedb(scramble: &str, solution: &Solution) -> String {
+    let mut lines = vec![scramble.trim().to_string(), "".to_string()];
+    for (step, alg) in solution.steps.iter().zip(solution.algs.iter()) {
+        lines.push(format!("{} // {}", alg.notation(), step.label()));
+    }
+    lines.join("\n")
+}
+
+/// Reads a reconstruction written by `to_cubedb`, returning the scramble and solution
+#[pyfunction]
+pub fn from_cubedb(text: &str) -> PyResult<(String, Solution)> {
+    let mut lines = text.lines().map(|l| l.trim()).filter(|l| !l.is_empty());
+    let scramble = lines
+        .next()
+        .ok_or_else(|| PyValueError::new_err("Missing scramble"))?
+        .to_string();
+    Algorithm::new(&scramble)?;
+    let mut steps: Vec<StepInfo> = vec![];
+    let mut algs = vec![];
+    for line in lines {
+        let (moves, label) = line
+            .split_once("//")
+            .ok_or_else(|| PyValueError::new_err(format!("Missing step label: {}", line)))?;
+        let step = parse_label(label, steps.last())?;
+        steps.push(step);
+        algs.push(Algorithm::new(moves.trim())?);
+    }
+    Ok((scramble, Solution { steps, algs }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cubedb_round_trip() {
+        let text = "R' U' F D2 L B' U R2 F'\n\
+            \n\
+            F (B) // eofb\n\
+            U R2 (L') // drud\n\
+            R2 U' F2 // htr\n\
+            U2 R2 F2 // finish";
+        let (scramble, solution) = from_cubedb(text).unwrap();
+        assert_eq!(scramble, "R' U' F D2 L B' U R2 F'");
+        assert_eq!(solution.steps.len(), 4);
+        assert_eq!(solution.steps[1].kind, "dr");
+        assert_eq!(solution.steps[1].variant, "ud");
+        assert_eq!(solution.steps[2].kind, "htr");
+        assert_eq!(solution.steps[2].variant, "ud");
+        assert_eq!(format!("{}", solution.algs[1].0), "U R2 (L')");
+        assert_eq!(to_cubedb(&scramble, &solution), text);
+
+        let text = "R' U' F D2 L B' U R2 F'\n\nM' U (Rw) // eofb";
+        let (scramble, solution) = from_cubedb(text).unwrap();
+        assert_eq!(to_cubedb(&scramble, &solution), text);
+    }
+
+    #[test]
+    fn test_cubedb_errors() {
+        assert!(from_cubedb("").is_err());
+        assert!(from_cubedb("R U\nF").is_err());
+        assert!(from_cubedb("R U\nF // xcross").is_err());
+        assert!(from_cubedb("R U\nF // eoq").is_err());
+        assert!(from_cubedb("R U\nF // drxy").is_err());
+    }
+}
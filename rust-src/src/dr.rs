@@ -1,16 +1,21 @@
 use crate::eo::{EOFB, EORL, EOUD};
-use crate::solver::{solve_step, step_config};
+use crate::solver::{solve_step, step_config, CaseCap, StepFilters};
 use crate::Visibility::{Any, BadFace, BadPiece};
 use crate::{
-    Algorithm, DrawableCorner, Solvable, EDGE_FB_FACELETS, EDGE_RL_FACELETS, EDGE_UD_FACELETS,
+    Algorithm, Cube, CubeView, DrawableCorner, Solvable, EDGE_FB_FACELETS, EDGE_RL_FACELETS,
+    EDGE_UD_FACELETS,
 };
-use cubelib::cube::turn::TransformableMut;
-use cubelib::cube::{Corner, Cube333, Transformation333};
+use cubelib::algs::Algorithm as LibAlgorithm;
+use cubelib::cube::turn::{ApplyAlgorithm, Direction, TransformableMut};
+use cubelib::cube::{Corner, Cube333, Transformation333, Turn333};
 use cubelib::defs::{NissSwitchType, StepKind};
+use cubelib::solver::solution::Solution;
+use cubelib::solver_new::group::{StepPredicate, StepPredicateResult};
 use cubelib::steps::coord::Coord;
 use cubelib::steps::dr::coords::DRUDEOFBCoord;
 use cubelib::steps::eo::coords::BadEdgeCount;
-use pyo3::PyResult;
+use pyo3::exceptions::PyValueError;
+use pyo3::{pyfunction, PyResult};
 
 pub struct DRUD;
 impl Solvable for DRUD {
@@ -25,17 +30,12 @@ impl Solvable for DRUD {
         EORL.is_solved(cube) || EOFB.is_solved(cube)
     }
     fn case_name(&self, cube: &Cube333) -> String {
-        let bad_corner_count = cube
-            .corners
-            .get_corners()
-            .into_iter()
-            .filter(|c: &Corner| c.orientation != 0)
-            .count();
+        let bad_corner_count = bad_corner_count(cube);
         let bad_edge_count = cube.count_bad_edges_lr() + cube.count_bad_edges_fb();
         format!("{}c{}e", bad_corner_count, bad_edge_count)
     }
-    fn edge_visibility(&self, cube: &Cube333, pos: usize, facelet: u8) -> u8 {
-        let e = cube.edges.get_edges()[pos];
+    fn edge_visibility(&self, cube: &CubeView, pos: usize, facelet: u8) -> u8 {
+        let e = cube.edges[pos];
         let mut v = Any as u8;
         if !e.oriented_fb || !e.oriented_rl {
             v |= BadPiece as u8;
@@ -52,8 +52,8 @@ impl Solvable for DRUD {
         }
         v
     }
-    fn corner_visibility(&self, cube: &Cube333, pos: usize, facelet: u8) -> u8 {
-        let c = cube.corners.get_corners()[pos];
+    fn corner_visibility(&self, cube: &CubeView, pos: usize, facelet: u8) -> u8 {
+        let c = cube.corners[pos];
         let mut v = Any as u8;
         if !c.oriented_ud(pos as u8) {
             v |= BadPiece as u8;
@@ -63,22 +63,31 @@ impl Solvable for DRUD {
         }
         v
     }
-    fn solve(&self, cube: &Cube333, count: usize) -> PyResult<Vec<Algorithm>> {
-        solve_step(
-            cube,
-            step_config(StepKind::DR, "ud", NissSwitchType::Never),
-            count,
-            true,
-        )
+    fn solve_filtered(
+        &self,
+        cube: &Cube333,
+        count: usize,
+        filters: StepFilters,
+    ) -> PyResult<Vec<Algorithm>> {
+        solve_dr(cube, "ud", count, filters)
     }
 }
 
+impl DRUD {
+    /// True if DR is already solved on a cube where it could be started, so the DR
+    /// costs no moves
+    pub fn is_free(&self, cube: &Cube333) -> bool {
+        self.is_eligible(cube) && self.is_solved(cube)
+    }
+}
 pub struct DRFB;
 impl Solvable for DRFB {
     fn is_solved(&self, cube: &Cube333) -> bool {
-        let mut cube = cube.clone();
-        cube.transform(Transformation333::X);
-        DRUD.is_solved(&cube)
+        let corners = cube.corners.get_corners();
+        cube.count_bad_edges_ud() == 0
+            && cube.count_bad_edges_lr() == 0
+            && (0..8).all(|pos| corners[pos].oriented_fb(pos as u8))
+            && edges_in_slice(cube, [1, 3, 9, 11])
     }
     fn is_eligible(&self, cube: &Cube333) -> bool {
         EORL.is_solved(cube) || EOUD.is_solved(cube)
@@ -88,8 +97,8 @@ impl Solvable for DRFB {
         cube.transform(Transformation333::X);
         DRUD.case_name(&cube)
     }
-    fn edge_visibility(&self, cube: &Cube333, pos: usize, facelet: u8) -> u8 {
-        let e = cube.edges.get_edges()[pos];
+    fn edge_visibility(&self, cube: &CubeView, pos: usize, facelet: u8) -> u8 {
+        let e = cube.edges[pos];
         let mut v = Any as u8;
         if !e.oriented_ud || !e.oriented_rl {
             v |= BadPiece as u8;
@@ -106,8 +115,8 @@ impl Solvable for DRFB {
         }
         v
     }
-    fn corner_visibility(&self, cube: &Cube333, pos: usize, facelet: u8) -> u8 {
-        let c = cube.corners.get_corners()[pos];
+    fn corner_visibility(&self, cube: &CubeView, pos: usize, facelet: u8) -> u8 {
+        let c = cube.corners[pos];
         let mut v = Any as u8;
         if !c.oriented_fb(pos as u8) {
             v |= BadPiece as u8;
@@ -117,21 +126,30 @@ impl Solvable for DRFB {
         }
         v
     }
-    fn solve(&self, cube: &Cube333, count: usize) -> PyResult<Vec<Algorithm>> {
-        solve_step(
-            cube,
-            step_config(StepKind::DR, "fb", NissSwitchType::Never),
-            count,
-            true,
-        )
+    fn solve_filtered(
+        &self,
+        cube: &Cube333,
+        count: usize,
+        filters: StepFilters,
+    ) -> PyResult<Vec<Algorithm>> {
+        solve_dr(cube, "fb", count, filters)
+    }
+}
+impl DRFB {
+    /// True if DR is already solved on a cube where it could be started, so the DR
+    /// costs no moves
+    pub fn is_free(&self, cube: &Cube333) -> bool {
+        self.is_eligible(cube) && self.is_solved(cube)
     }
 }
 pub struct DRRL;
 impl Solvable for DRRL {
     fn is_solved(&self, cube: &Cube333) -> bool {
-        let mut cube = cube.clone();
-        cube.transform(Transformation333::Z);
-        DRUD.is_solved(&cube)
+        let corners = cube.corners.get_corners();
+        cube.count_bad_edges_ud() == 0
+            && cube.count_bad_edges_fb() == 0
+            && (0..8).all(|pos| corners[pos].oriented_rl(pos as u8))
+            && edges_in_slice(cube, [0, 2, 8, 10])
     }
     fn is_eligible(&self, cube: &Cube333) -> bool {
         EOUD.is_solved(cube) || EOFB.is_solved(cube)
@@ -141,8 +159,8 @@ impl Solvable for DRRL {
         cube.transform(Transformation333::Z);
         DRUD.case_name(&cube)
     }
-    fn edge_visibility(&self, cube: &Cube333, pos: usize, facelet: u8) -> u8 {
-        let e = cube.edges.get_edges()[pos];
+    fn edge_visibility(&self, cube: &CubeView, pos: usize, facelet: u8) -> u8 {
+        let e = cube.edges[pos];
         let mut v = Any as u8;
         if !e.oriented_fb || !e.oriented_ud {
             v |= BadPiece as u8;
@@ -159,8 +177,8 @@ impl Solvable for DRRL {
         }
         v
     }
-    fn corner_visibility(&self, cube: &Cube333, pos: usize, facelet: u8) -> u8 {
-        let c = cube.corners.get_corners()[pos];
+    fn corner_visibility(&self, cube: &CubeView, pos: usize, facelet: u8) -> u8 {
+        let c = cube.corners[pos];
         let mut v = Any as u8;
         if !c.oriented_rl(pos as u8) {
             v |= BadPiece as u8;
@@ -170,23 +188,209 @@ impl Solvable for DRRL {
         }
         v
     }
-    fn solve(&self, cube: &Cube333, count: usize) -> PyResult<Vec<Algorithm>> {
-        solve_step(
-            cube,
-            step_config(StepKind::DR, "lr", NissSwitchType::Never),
-            count,
-            true,
-        )
+    fn solve_filtered(
+        &self,
+        cube: &Cube333,
+        count: usize,
+        filters: StepFilters,
+    ) -> PyResult<Vec<Algorithm>> {
+        solve_dr(cube, "rl", count, filters)
     }
 }
+impl DRRL {
+    /// True if DR is already solved on a cube where it could be started, so the DR
+    /// costs no moves
+    pub fn is_free(&self, cube: &Cube333) -> bool {
+        self.is_eligible(cube) && self.is_solved(cube)
+    }
+}
+
+// The edges belonging to the slice are all somewhere in the slice
+fn edges_in_slice(cube: &Cube333, slice: [usize; 4]) -> bool {
+    let edges = cube.edges.get_edges();
+    slice
+        .iter()
+        .all(|pos| slice.contains(&(edges[*pos].id as usize)))
+}
+
+fn bad_corner_count(cube: &Cube333) -> usize {
+    cube.corners
+        .get_corners()
+        .into_iter()
+        .filter(|c: &Corner| c.orientation != 0)
+        .count()
+}
+
+// Number of bad corners in the DR `case_name`, e.g. 4 for "4c2e"
+fn case_corner_count(step: &dyn Solvable, cube: &Cube333) -> Option<usize> {
+    let name = step.case_name(cube);
+    let digits: String = name.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+// Index of the first move of the solution's final trigger: its last quarter turn off
+// the DR axis, extended back over axis moves to a quarter turn of the same face, so
+// "R U2 R'" is one trigger. 0 if there is none.
+fn trigger_start(moves: &[Turn333], axis_faces: &str) -> usize {
+    let face = |t: &Turn333| format!("{}", t).chars().next().unwrap();
+    let is_trigger = |t: &Turn333| t.dir != Direction::Half && !axis_faces.contains(face(t));
+    let last = match moves.iter().rposition(is_trigger) {
+        Some(last) => last,
+        None => return 0,
+    };
+    let before = moves[..last]
+        .iter()
+        .rposition(|t| !axis_faces.contains(face(t)));
+    match before {
+        Some(i)
+            if i + 1 < last && face(&moves[i]) == face(&moves[last]) && is_trigger(&moves[i]) =>
+        {
+            i
+        }
+        _ => last,
+    }
+}
+
+// Accepts solutions whose final trigger is applied to a case with the given corner count
+struct FilterTriggerCorners {
+    cube: Cube333,
+    step: &'static (dyn Solvable + Sync),
+    axis_faces: &'static str,
+    corners: usize,
+}
+
+impl StepPredicate for FilterTriggerCorners {
+    fn check_solution(&self, solution: &Solution) -> StepPredicateResult {
+        let alg: LibAlgorithm = solution.clone().into();
+        let moves = alg.to_uninverted().normal_moves;
+        let mut case = self.cube.clone();
+        case.apply_alg(&LibAlgorithm {
+            normal_moves: moves[..trigger_start(&moves, self.axis_faces)].to_vec(),
+            inverse_moves: vec![],
+        });
+        if case_corner_count(self.step, &case) == Some(self.corners) {
+            StepPredicateResult::Accepted
+        } else {
+            StepPredicateResult::Rejected
+        }
+    }
+}
+
+/// Only accepts DRs whose final trigger is applied to a case with the given number of
+/// bad corners, as counted by `case_name`: "R U2 R'" from a 4c4e case counts as 4c
+pub fn corner_count_filter(
+    cube: &Cube333,
+    axis: &str,
+    corners: usize,
+) -> PyResult<Box<dyn StepPredicate>> {
+    let (step, axis_faces): (&'static (dyn Solvable + Sync), &'static str) = match axis {
+        "ud" => (&DRUD, "UD"),
+        "fb" => (&DRFB, "FB"),
+        "rl" => (&DRRL, "RL"),
+        _ => return Err(PyValueError::new_err(format!("Unknown axis: {}", axis))),
+    };
+    Ok(Box::new(FilterTriggerCorners {
+        cube: cube.clone(),
+        step,
+        axis_faces,
+        corners,
+    }))
+}
+
+/// The DR axes that can be started from the cube's EO, in ud, fb, rl order
+#[pyfunction]
+pub fn available_dr_axes(cube: &Cube) -> Vec<String> {
+    let axes: [(&str, &dyn Solvable); 3] = [("ud", &DRUD), ("fb", &DRFB), ("rl", &DRRL)];
+    axes.iter()
+        .filter(|(_, step)| step.is_eligible(&cube.0))
+        .map(|(axis, _)| axis.to_string())
+        .collect()
+}
+
+/// Per-piece DR status for `axis`, combining the visibility flags of every facelet:
+/// edges are bad if misoriented on either EO axis DR needs, corners if twisted
+/// off the DR axis
+#[pyfunction]
+pub fn eodr_overlay(cube: &Cube, axis: &str) -> PyResult<(Vec<u8>, Vec<u8>)> {
+    let step: &dyn Solvable = match axis {
+        "ud" => &DRUD,
+        "fb" => &DRFB,
+        "rl" => &DRRL,
+        _ => return Err(PyValueError::new_err(format!("Unknown axis: {}", axis))),
+    };
+    let view = CubeView::new(&cube.0);
+    let edges = (0..12)
+        .map(|i| (0..2).fold(0, |v, f| v | step.edge_visibility(&view, i, f)))
+        .collect();
+    let corners = (0..8)
+        .map(|i| (0..3).fold(0, |v, f| v | step.corner_visibility(&view, i, f)))
+        .collect();
+    Ok((edges, corners))
+}
+
+/// Accepts at most `per_subset` DRs on `axis` leading to each HTR subset. The subset
+/// is read with the DR axis turned to UD, the frame `get_dr_subset` measures in.
+pub fn subset_cap(axis: &str, per_subset: usize) -> PyResult<CaseCap> {
+    let transform = match axis {
+        "ud" => None,
+        "fb" => Some(Transformation333::X),
+        "rl" => Some(Transformation333::Z),
+        _ => return Err(PyValueError::new_err(format!("Unknown axis: {}", axis))),
+    };
+    Ok(CaseCap::new(
+        per_subset,
+        move |c: &Cube333, _alg: &LibAlgorithm| {
+            let mut c = c.clone();
+            if let Some(t) = transform {
+                c.transform(t);
+            }
+            c.get_dr_subset().map(|s| s.to_string()).unwrap_or_default()
+        },
+    ))
+}
+
+pub fn solve_dr(
+    cube: &Cube333,
+    variant: &str,
+    count: usize,
+    filters: StepFilters,
+) -> PyResult<Vec<Algorithm>> {
+    let cfg_variant = match variant {
+        "ud" => "ud",
+        "fb" => "fb",
+        "rl" => "lr",
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "Unknown variant '{}' for dr",
+                variant
+            )))
+        }
+    };
+    solve_step(
+        cube,
+        step_config(StepKind::DR, cfg_variant, NissSwitchType::Never),
+        count,
+        true,
+        filters,
+    )
+}
 
 #[cfg(test)]
 mod tests {
-    use crate::dr::DRUD;
-    use crate::{Algorithm, Cube, Solvable};
+    use crate::dr::{
+        available_dr_axes, corner_count_filter, eodr_overlay, solve_dr, subset_cap, trigger_start,
+        DRFB, DRRL, DRUD,
+    };
+    use crate::solver::StepFilters;
+    use crate::Visibility::{Any, BadPiece};
+    use crate::{Algorithm, Cube, CubeView, Solvable, SolveOptions, StepInfo};
+    use cubelib::algs::Algorithm as LibAlgorithm;
+    use cubelib::cube::turn::{ApplyAlgorithm, TransformableMut};
+    use cubelib::cube::{Cube333, Transformation333};
     use cubelib::defs::StepKind::DR;
     use cubelib::steps::coord::Coord;
     use cubelib::steps::dr::coords::DRUDEOFBCoord;
+    use std::collections::HashMap;
 
     #[test]
     fn test_drud_edge_visibility() {
@@ -194,12 +398,32 @@ mod tests {
         let drud = DRUD;
         for pos in 4..8 {
             for face in 0..2 {
-                let viz = DRUD.edge_visibility(&cube.0, pos, face);
+                let viz = DRUD.edge_visibility(&CubeView::new(&cube.0), pos, face);
                 print!("{}", viz);
             }
         }
     }
 
+    #[test]
+    fn test_eodr_overlay() {
+        // R keeps EO on fb and ud but breaks it on rl, and twists the R layer corners
+        let cube = Cube::new("R".to_string()).unwrap();
+        let (edges, corners) = eodr_overlay(&cube, "ud").unwrap();
+        let bad_edges: Vec<usize> = (0..12)
+            .filter(|&i| edges[i] & BadPiece as u8 != 0)
+            .collect();
+        let bad_corners: Vec<usize> = (0..8)
+            .filter(|&i| corners[i] & BadPiece as u8 != 0)
+            .collect();
+        assert_eq!(bad_edges, vec![1, 4, 6, 9]);
+        assert_eq!(bad_corners, vec![1, 2, 5, 6]);
+        assert!(edges.iter().all(|v| v & Any as u8 != 0));
+
+        let (edges, _) = eodr_overlay(&cube, "rl").unwrap();
+        assert!(edges.iter().all(|v| v & BadPiece as u8 == 0));
+        assert!(eodr_overlay(&cube, "xy").is_err());
+    }
+
     #[test]
     fn test_drud_coord() {
         let mut cube = Cube::new("".to_string()).unwrap();
@@ -210,4 +434,187 @@ mod tests {
         coord = DRUDEOFBCoord::from(&cube.0);
         assert_eq!(coord.val(), 0);
     }
+
+    #[test]
+    fn test_is_solved_matches_transformed() {
+        let algs = [
+            "",
+            "R",
+            "U",
+            "F",
+            "R U2 F2 U2 R",
+            "F R2 U2 B'",
+            "U L2 D' F2",
+            "R L' U2 R' L",
+            "R U F",
+        ];
+        let transformed = |cube: &Cube333, t: Transformation333| {
+            let mut cube = cube.clone();
+            cube.transform(t);
+            DRUD.is_solved(&cube)
+        };
+        let cubes: Vec<Cube333> = algs
+            .iter()
+            .map(|alg| Cube::new(alg.to_string()).unwrap().0)
+            .collect();
+        for (alg, cube) in algs.iter().zip(cubes.iter()) {
+            assert_eq!(
+                DRFB.is_solved(cube),
+                transformed(cube, Transformation333::X),
+                "{}",
+                alg
+            );
+            assert_eq!(
+                DRRL.is_solved(cube),
+                transformed(cube, Transformation333::Z),
+                "{}",
+                alg
+            );
+        }
+    }
+
+    #[test]
+    fn test_dr_target_corners() {
+        let cube = Cube::new("R U R' F2 D L2 U B2 R2".to_string()).unwrap();
+        let filters = vec![corner_count_filter(&cube.0, "ud", 4).unwrap()];
+        let solutions = solve_dr(&cube.0, "ud", 3, filters.into()).unwrap();
+        assert!(!solutions.is_empty());
+        for alg in solutions {
+            let moves = alg.0.to_uninverted().normal_moves;
+            let mut c = cube.clone();
+            c.apply(&alg);
+            assert!(DRUD.is_solved(&c.0));
+            let mut case = cube.clone();
+            case.0.apply_alg(&LibAlgorithm {
+                normal_moves: moves[..trigger_start(&moves, "UD")].to_vec(),
+                inverse_moves: vec![],
+            });
+            assert_eq!(DRUD.case_name(&case.0).split('c').next(), Some("4"));
+        }
+        assert!(corner_count_filter(&cube.0, "xy", 4).is_err());
+        let start = |s: &str| trigger_start(&Algorithm::new(s).unwrap().0.normal_moves, "UD");
+        assert_eq!(start("F2 R U2 R'"), 1);
+        assert_eq!(start("F2 U R"), 2);
+        assert_eq!(start("R U R2 D2"), 0);
+        assert_eq!(start("U2 D"), 0);
+        assert!(solve_dr(&cube.0, "xy", 3, StepFilters::default()).is_err());
+    }
+
+    #[test]
+    fn test_dr_per_subset() {
+        let cube = Cube::new("R U R' F2 D L2 U B2 R2".to_string()).unwrap();
+        let filters = StepFilters {
+            caps: vec![subset_cap("ud", 2).unwrap()],
+            ..Default::default()
+        };
+        let solutions = solve_dr(&cube.0, "ud", 20, filters).unwrap();
+        assert!(!solutions.is_empty());
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for alg in solutions {
+            let mut c = cube.clone();
+            c.apply(&alg);
+            *counts
+                .entry(c.0.get_dr_subset().unwrap().to_string())
+                .or_default() += 1;
+        }
+        assert!(counts.values().all(|n| *n <= 2));
+
+        let step = StepInfo::new("eo", "fb").unwrap();
+        assert!(step
+            .solve_with(
+                &cube,
+                1,
+                &SolveOptions {
+                    per_subset: Some(1),
+                    ..Default::default()
+                }
+            )
+            .is_err());
+        assert!(subset_cap("xy", 1).is_err());
+    }
+
+    #[test]
+    fn test_dr_per_subset_off_ud() {
+        // With the FB axis turned to UD, one DR per subset, and more than one subset
+        let cube = Cube::new("R U F' D2 L B".to_string()).unwrap();
+        let eo_alg = StepInfo::new("eo", "ud")
+            .unwrap()
+            .solve_with(&cube, 1, &SolveOptions::default())
+            .unwrap()
+            .remove(0);
+        let mut eo = cube.clone();
+        eo.apply(&eo_alg);
+        let options = SolveOptions {
+            per_subset: Some(1),
+            ..Default::default()
+        };
+        let solutions = StepInfo::new("dr", "fb")
+            .unwrap()
+            .solve_with(&eo, 10, &options)
+            .unwrap();
+        let subsets: Vec<String> = solutions
+            .iter()
+            .map(|alg| {
+                let mut c = eo.clone();
+                c.apply(alg);
+                c.0.transform(Transformation333::X);
+                c.0.get_dr_subset().unwrap().to_string()
+            })
+            .collect();
+        assert!(subsets.len() > 1);
+        for (i, subset) in subsets.iter().enumerate() {
+            assert!(!subsets[..i].contains(subset));
+        }
+    }
+
+    #[test]
+    fn test_axis_solutions_apply_to_original_cube() {
+        let cube = Cube::new("R U F' D2 L B".to_string()).unwrap();
+        let axes = [("fb", "x", &DRFB as &dyn Solvable), ("rl", "z", &DRRL)];
+        for (variant, rotation, step) in axes {
+            let eo_step = StepInfo::new("eo", if variant == "fb" { "ud" } else { "fb" }).unwrap();
+            let eo_alg = eo_step
+                .solve_with(&cube, 1, &SolveOptions::default())
+                .unwrap()
+                .remove(0);
+            let mut eo = cube.clone();
+            eo.apply(&eo_alg);
+            let solutions = solve_dr(&eo.0, variant, 3, StepFilters::default()).unwrap();
+            assert!(!solutions.is_empty());
+            for alg in solutions {
+                let mut c = eo.clone();
+                c.apply(&alg);
+                assert!(step.is_solved(&c.0));
+            }
+
+            // A UD DR of the rotated cube, rewritten back to the original frame
+            let mut rotated = eo.clone();
+            rotated.transform(rotation).unwrap();
+            for alg in solve_dr(&rotated.0, "ud", 3, StepFilters::default()).unwrap() {
+                let mut c = eo.clone();
+                c.apply(&Cube::transform_solution_frame(rotation, &alg).unwrap());
+                assert!(step.is_solved(&c.0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_free_dr() {
+        let cube = Cube::new("R2 U F2 D' L2".to_string()).unwrap();
+        assert!(DRUD.is_free(&cube.0));
+        assert!(!DRFB.is_free(&cube.0));
+        assert!(!DRRL.is_free(&cube.0));
+        let cube = Cube::new("F R2 U2 L2 B'".to_string()).unwrap();
+        assert!(DRFB.is_free(&cube.0));
+        assert!(!DRUD.is_free(&cube.0));
+        assert!(!DRUD.is_free(&Cube::new("R".to_string()).unwrap().0));
+    }
+
+    #[test]
+    fn test_available_dr_axes() {
+        let axes = |s: &str| available_dr_axes(&Cube::new(s.to_string()).unwrap());
+        assert_eq!(axes("F R"), vec!["fb", "rl"]);
+        assert_eq!(axes(""), vec!["ud", "fb", "rl"]);
+        assert!(axes("F R U").is_empty());
+    }
 }
@@ -1,10 +1,11 @@
-use crate::solver::{solve_step, step_config};
+use crate::solver::{solve_step, step_config, StepFilters};
 use crate::Visibility::{Any, BadFace, BadPiece};
-use crate::{Algorithm, Solvable};
-use cubelib::cube::Cube333;
+use crate::{Algorithm, Cube, CubeView, Solvable};
+use cubelib::cube::{Cube333, Edge};
 use cubelib::defs::{NissSwitchType, StepKind};
 use cubelib::steps::eo::coords::BadEdgeCount;
-use pyo3::PyResult;
+use pyo3::exceptions::PyValueError;
+use pyo3::{pyfunction, PyResult};
 
 pub struct EOUD;
 impl Solvable for EOUD {
@@ -18,25 +19,69 @@ impl Solvable for EOUD {
     fn case_name(&self, cube: &Cube333) -> String {
         format!("{}e", cube.count_bad_edges_ud())
     }
-    fn edge_visibility(&self, cube: &Cube333, pos: usize, _facelet: u8) -> u8 {
-        if !cube.edges.get_edges()[pos].oriented_ud {
+    fn edge_visibility(&self, cube: &CubeView, pos: usize, _facelet: u8) -> u8 {
+        if !cube.edges[pos].oriented_ud {
             BadFace as u8 | BadPiece as u8
         } else {
             Any as u8
         }
     }
-    fn corner_visibility(&self, _cube: &Cube333, _pos: usize, _facelet: u8) -> u8 {
+    fn corner_visibility(&self, _cube: &CubeView, _pos: usize, _facelet: u8) -> u8 {
         Any as u8
     }
-    fn solve(&self, cube: &Cube333, count: usize) -> PyResult<Vec<Algorithm>> {
+    fn solve_filtered(
+        &self,
+        cube: &Cube333,
+        count: usize,
+        filters: StepFilters,
+    ) -> PyResult<Vec<Algorithm>> {
         solve_step(
             cube,
             step_config(StepKind::EO, "ud", NissSwitchType::Always),
             count,
             true,
+            filters,
         )
     }
 }
+impl EOUD {
+    /// Names the quick EO cases: "4-flip layer" when the bad edges are exactly the
+    /// U or D layer (one move), and "2-flip adjacent", "2-flip opposite" or "2-flip"
+    /// for two bad edges, depending on whether they share a U/D layer.
+    pub fn fast_case(&self, cube: &Cube333) -> Option<String> {
+        let bad: Vec<usize> = cube
+            .edges
+            .get_edges()
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| !e.oriented_ud)
+            .map(|(pos, _)| pos)
+            .collect();
+        let name = match bad[..] {
+            [0, 1, 2, 3] | [8, 9, 10, 11] => "4-flip layer",
+            [a, b] if layer(a).is_some() && layer(a) == layer(b) => {
+                if (b - a) % 2 == 1 {
+                    "2-flip adjacent"
+                } else {
+                    "2-flip opposite"
+                }
+            }
+            [_, _] => "2-flip",
+            _ => return None,
+        };
+        Some(name.to_string())
+    }
+}
+
+// 0 for the U layer, 1 for the D layer, None for the E slice
+fn layer(pos: usize) -> Option<usize> {
+    match pos {
+        0..=3 => Some(0),
+        8..=11 => Some(1),
+        _ => None,
+    }
+}
+
 pub struct EOFB;
 impl Solvable for EOFB {
     fn is_solved(&self, cube: &Cube333) -> bool {
@@ -49,22 +94,28 @@ impl Solvable for EOFB {
     fn case_name(&self, cube: &Cube333) -> String {
         format!("{}e", cube.count_bad_edges_fb())
     }
-    fn edge_visibility(&self, cube: &Cube333, pos: usize, _facelet: u8) -> u8 {
-        if !cube.edges.get_edges()[pos].oriented_fb {
+    fn edge_visibility(&self, cube: &CubeView, pos: usize, _facelet: u8) -> u8 {
+        if !cube.edges[pos].oriented_fb {
             BadFace as u8 | BadPiece as u8
         } else {
             Any as u8
         }
     }
-    fn corner_visibility(&self, _cube: &Cube333, _pos: usize, _facelet: u8) -> u8 {
+    fn corner_visibility(&self, _cube: &CubeView, _pos: usize, _facelet: u8) -> u8 {
         Any as u8
     }
-    fn solve(&self, cube: &Cube333, count: usize) -> PyResult<Vec<Algorithm>> {
+    fn solve_filtered(
+        &self,
+        cube: &Cube333,
+        count: usize,
+        filters: StepFilters,
+    ) -> PyResult<Vec<Algorithm>> {
         solve_step(
             cube,
             step_config(StepKind::EO, "fb", NissSwitchType::Always),
             count,
             true,
+            filters,
         )
     }
 }
@@ -80,30 +131,109 @@ impl Solvable for EORL {
     fn case_name(&self, cube: &Cube333) -> String {
         format!("{}e", cube.count_bad_edges_lr())
     }
-    fn edge_visibility(&self, cube: &Cube333, pos: usize, _facelet: u8) -> u8 {
-        if !cube.edges.get_edges()[pos].oriented_rl {
+    fn edge_visibility(&self, cube: &CubeView, pos: usize, _facelet: u8) -> u8 {
+        if !cube.edges[pos].oriented_rl {
             BadFace as u8 | BadPiece as u8
         } else {
             Any as u8 as u8
         }
     }
-    fn corner_visibility(&self, _cube: &Cube333, _pos: usize, _facelet: u8) -> u8 {
+    fn corner_visibility(&self, _cube: &CubeView, _pos: usize, _facelet: u8) -> u8 {
         Any as u8
     }
-    fn solve(&self, cube: &Cube333, count: usize) -> PyResult<Vec<Algorithm>> {
+    fn solve_filtered(
+        &self,
+        cube: &Cube333,
+        count: usize,
+        filters: StepFilters,
+    ) -> PyResult<Vec<Algorithm>> {
         solve_step(
             cube,
             step_config(StepKind::EO, "lr", NissSwitchType::Always),
             count,
             true,
+            filters,
         )
     }
 }
 
+fn is_oriented(e: &Edge, axis: &str) -> PyResult<bool> {
+    match axis {
+        "ud" => Ok(e.oriented_ud),
+        "fb" => Ok(e.oriented_fb),
+        "rl" | "lr" => Ok(e.oriented_rl),
+        _ => Err(PyValueError::new_err(format!(
+            "Unknown variant '{}' for eo",
+            axis
+        ))),
+    }
+}
+
+/// For each edge, whether it is oriented on axis `a` and on axis `b`
+#[pyfunction]
+pub fn eo_both_axes(cube: &Cube, a: &str, b: &str) -> PyResult<Vec<(bool, bool)>> {
+    cube.0
+        .edges
+        .get_edges()
+        .iter()
+        .map(|e| Ok((is_oriented(e, a)?, is_oriented(e, b)?)))
+        .collect()
+}
+
+/// The EO axis with the fewest bad edges, and that count. Ties go to the first of
+/// fb, rl, ud.
+#[pyfunction]
+pub fn min_eo(cube: &Cube) -> (String, u8) {
+    let counts = [
+        ("fb", cube.0.count_bad_edges_fb()),
+        ("rl", cube.0.count_bad_edges_lr()),
+        ("ud", cube.0.count_bad_edges_ud()),
+    ];
+    let (axis, count) = counts
+        .iter()
+        .fold(counts[0], |best, c| if c.1 < best.1 { *c } else { best });
+    (axis.to_string(), count as u8)
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn test_piece_roles() {
         let x = (1, 2, 3);
     }
+
+    #[test]
+    fn test_eo_both_axes() {
+        let cube = Cube::new("F R".to_string()).unwrap();
+        let both = eo_both_axes(&cube, "fb", "ud").unwrap();
+        assert_eq!(both.len(), 12);
+        let bad_fb = both.iter().filter(|(fb, _)| !fb).count();
+        let bad_ud = both.iter().filter(|(_, ud)| !ud).count();
+        assert_eq!(bad_fb, cube.0.count_bad_edges_fb() as usize);
+        assert_eq!(bad_ud, cube.0.count_bad_edges_ud() as usize);
+        assert_ne!(bad_fb, bad_ud);
+        assert!(eo_both_axes(&cube, "fb", "xy").is_err());
+    }
+
+    #[test]
+    fn test_min_eo() {
+        let min = |s: &str| min_eo(&Cube::new(s.to_string()).unwrap());
+        assert_eq!(min(""), ("fb".to_string(), 0));
+        assert_eq!(min("F"), ("rl".to_string(), 0));
+        assert_eq!(min("F R"), ("ud".to_string(), 0));
+        assert_eq!(min("F R U"), ("fb".to_string(), 4));
+    }
+
+    #[test]
+    fn test_fast_case() {
+        let fast_case = |s: &str| EOUD.fast_case(&Cube::new(s.to_string()).unwrap().0);
+        assert_eq!(fast_case("U"), Some("4-flip layer".to_string()));
+        assert_eq!(fast_case("D'"), Some("4-flip layer".to_string()));
+        assert_eq!(fast_case("U R2 U'"), Some("2-flip".to_string()));
+        assert_eq!(fast_case("U R2 U' R2"), Some("2-flip adjacent".to_string()));
+        assert_eq!(fast_case(""), None);
+        assert_eq!(fast_case("U D"), None);
+    }
 }
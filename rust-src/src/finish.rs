@@ -1,13 +1,125 @@
-use crate::solver::{solve_step, step_config};
+use crate::htr::HTRUD;
+use crate::pruning::{finish_distance, lower_bound};
+use crate::solver::{solve_step, step_config, StepFilters};
 use crate::Visibility::{Any, BadFace, BadPiece};
-use crate::{Algorithm, Solvable};
+use crate::{Algorithm, Cube, CubeView, Solvable};
+use cubelib::algs::Algorithm as LibAlgorithm;
+use cubelib::cube::turn::ApplyAlgorithm;
 use cubelib::cube::Cube333;
 use cubelib::defs::{NissSwitchType, StepKind};
 use cubelib::steps::coord::Coord;
 use cubelib::steps::finish::coords::HTRFinishCoord;
-use pyo3::PyResult;
+use pyo3::exceptions::PyValueError;
+use pyo3::{pyfunction, PyResult};
+use std::str::FromStr;
 
 pub struct Finish;
+
+pub const EDGE_NAMES: [&str; 12] = [
+    "UB", "UR", "UF", "UL", "FR", "FL", "BR", "BL", "DF", "DR", "DB", "DL",
+];
+pub const CORNER_NAMES: [&str; 8] = ["UBL", "UBR", "UFR", "UFL", "DFL", "DFR", "DBR", "DBL"];
+
+impl Finish {
+    /// Solutions paired with a description of the cycles they resolve, e.g.
+    /// "solves UBL-UFR-UBR corner cycle"
+    pub fn solve_cycle_info(
+        &self,
+        cube: &Cube333,
+        count: usize,
+    ) -> PyResult<Vec<(Algorithm, String)>> {
+        let info = describe_cycles(cube);
+        Ok(self
+            .solve(cube, count)?
+            .into_iter()
+            .map(|alg| (alg, info.clone()))
+            .collect())
+    }
+
+    /// Finishing techniques the remaining case allows, as hints:
+    /// - "keyhole: FR slot with D layer" when the first two layers below some face are
+    ///   solved except for one corner-edge slot, which can be used to insert pieces
+    /// - "commutator: UBL-UFR-DFR corners" for a 3-cycle whose pieces do not all share a
+    ///   face, so a pure commutator can solve it
+    /// - "conjugate: UBL-UFR-UBR corners" for a 3-cycle within one face, which needs
+    ///   setup moves before a commutator
+    pub fn opportunities(&self, cube: &Cube333) -> Vec<String> {
+        let edges = cube.edges.get_edges();
+        let corners = cube.corners.get_corners();
+        let bad_edges: Vec<&str> = (0..12)
+            .filter(|i| edges[*i].id as usize != *i)
+            .map(|i| EDGE_NAMES[i])
+            .collect();
+        let bad_corners: Vec<&str> = (0..8)
+            .filter(|i| corners[*i].id as usize != *i)
+            .map(|i| CORNER_NAMES[i])
+            .collect();
+        let mut hints = vec![];
+        for (bottom, top) in [
+            ('D', 'U'),
+            ('U', 'D'),
+            ('F', 'B'),
+            ('B', 'F'),
+            ('R', 'L'),
+            ('L', 'R'),
+        ] {
+            let below = |names: &Vec<&str>| -> Vec<String> {
+                names
+                    .iter()
+                    .filter(|n| !n.contains(top))
+                    .map(|n| n.to_string())
+                    .collect()
+            };
+            if let ([edge], [corner]) = (&below(&bad_edges)[..], &below(&bad_corners)[..]) {
+                if !edge.contains(bottom)
+                    && corner.contains(bottom)
+                    && edge.chars().all(|c| corner.contains(c))
+                {
+                    hints.push(format!("keyhole: {} slot with {} layer", edge, bottom));
+                }
+            }
+        }
+        let edge_ids: Vec<u8> = edges.iter().map(|e| e.id).collect();
+        let corner_ids: Vec<u8> = corners.iter().map(|c| c.id).collect();
+        for (cycle_ids, names, kind) in [
+            (corner_ids, &CORNER_NAMES[..], "corners"),
+            (edge_ids, &EDGE_NAMES[..], "edges"),
+        ] {
+            for cycle in cycles(&cycle_ids).iter().filter(|c| c.len() == 3) {
+                let pieces: Vec<&str> = cycle.iter().map(|pos| names[*pos]).collect();
+                let one_face = pieces[0]
+                    .chars()
+                    .any(|f| pieces.iter().all(|p| p.contains(f)));
+                let technique = if one_face { "conjugate" } else { "commutator" };
+                hints.push(format!("{}: {} {}", technique, pieces.join("-"), kind));
+            }
+        }
+        hints
+    }
+}
+
+fn describe_cycles(cube: &Cube333) -> String {
+    let edge_ids: Vec<u8> = cube.edges.get_edges().iter().map(|e| e.id).collect();
+    let corner_ids: Vec<u8> = cube.corners.get_corners().iter().map(|c| c.id).collect();
+    let named = |cycle: &Vec<usize>, names: &[&str], kind: &str| {
+        let slots: Vec<&str> = cycle.iter().map(|pos| names[*pos]).collect();
+        format!("{} {} cycle", slots.join("-"), kind)
+    };
+    let mut parts: Vec<String> = cycles(&corner_ids)
+        .iter()
+        .map(|c| named(c, &CORNER_NAMES, "corner"))
+        .collect();
+    parts.extend(
+        cycles(&edge_ids)
+            .iter()
+            .map(|c| named(c, &EDGE_NAMES, "edge")),
+    );
+    if parts.is_empty() {
+        "solved".to_string()
+    } else {
+        format!("solves {}", parts.join(", "))
+    }
+}
 impl Solvable for Finish {
     fn is_solved(&self, cube: &Cube333) -> bool {
         HTRFinishCoord::from(cube).val() == 0
@@ -43,32 +155,141 @@ impl Solvable for Finish {
         format!("{}{}", c_string, e_string)
     }
 
-    fn edge_visibility(&self, cube: &Cube333, pos: usize, _facelet: u8) -> u8 {
+    fn edge_visibility(&self, cube: &CubeView, pos: usize, _facelet: u8) -> u8 {
         let mut v = Any as u8;
-        if cube.edges.get_edges()[pos].id as usize != pos {
+        if cube.edges[pos].id as usize != pos {
             v |= BadPiece as u8 | BadFace as u8;
         }
         v
     }
 
-    fn corner_visibility(&self, cube: &Cube333, pos: usize, _facelet: u8) -> u8 {
+    fn corner_visibility(&self, cube: &CubeView, pos: usize, _facelet: u8) -> u8 {
         let mut v = Any as u8;
-        if cube.corners.get_corners()[pos].id as usize != pos {
+        if cube.corners[pos].id as usize != pos {
             v |= BadPiece as u8 | BadFace as u8;
         }
         v
     }
-    fn solve(&self, cube: &Cube333, count: usize) -> PyResult<Vec<Algorithm>> {
+    fn solve_filtered(
+        &self,
+        cube: &Cube333,
+        count: usize,
+        filters: StepFilters,
+    ) -> PyResult<Vec<Algorithm>> {
         let mut cfg = step_config(StepKind::FIN, "", NissSwitchType::Never);
         cfg.max = Some(20);
-        solve_step(cube, cfg, count, false)
+        solve_step(cube, cfg, count, false, filters)
     }
 }
 
+/// Optimal move count to finish an HTR cube, looked up in a distance table of every
+/// HTR state that is built on first use
+#[pyfunction]
+pub fn finish_length(cube: &Cube) -> PyResult<u8> {
+    if !HTRUD.is_solved(&cube.0) {
+        return Err(PyValueError::new_err("HTR is not solved"));
+    }
+    finish_distance(&cube.0)
+        .map(|d| d as u8)
+        .ok_or_else(|| PyValueError::new_err("No solution found"))
+}
+
+pub const FACE_TURNS: [&str; 18] = [
+    "U", "U2", "U'", "D", "D2", "D'", "F", "F2", "F'", "B", "B2", "B'", "R", "R2", "R'", "L", "L2",
+    "L'",
+];
+
+/// True if every piece is in place and oriented
+pub fn is_fully_solved(cube: &Cube333) -> bool {
+    let solved = Cube333::default();
+    cube.edges.get_edges_raw() == solved.edges.get_edges_raw()
+        && cube.corners.get_corners_raw() == solved.corners.get_corners_raw()
+}
+
+/// Length of the shortest solution, if there is one with at most `max_depth` moves.
+/// The search skips states whose `lower_bound` is beyond the remaining depth, but
+/// its cost still grows quickly with `max_depth`.
+pub fn distance_within(cube: &Cube333, max_depth: usize) -> Option<usize> {
+    let turns: Vec<(char, LibAlgorithm)> = FACE_TURNS
+        .iter()
+        .map(|t| {
+            (
+                t.chars().next().unwrap(),
+                LibAlgorithm::from_str(t).unwrap(),
+            )
+        })
+        .collect();
+    (0..=max_depth).find(|depth| search(cube, *depth, None, &turns))
+}
+
+fn search(
+    cube: &Cube333,
+    depth: usize,
+    last_face: Option<char>,
+    turns: &[(char, LibAlgorithm)],
+) -> bool {
+    if depth == 0 {
+        return is_fully_solved(cube);
+    }
+    if lower_bound(cube) > depth {
+        return false;
+    }
+    turns.iter().any(|(face, turn)| {
+        if Some(*face) == last_face {
+            return false;
+        }
+        let mut c = cube.clone();
+        c.apply_alg(turn);
+        search(&c, depth - 1, Some(*face), turns)
+    })
+}
+
+// Permutation cycles, as lists of positions, ignoring pieces that are in place
+pub fn cycles(ids: &[u8]) -> Vec<Vec<usize>> {
+    let mut visited = vec![false; ids.len()];
+    let mut cycles = vec![];
+    for start in 0..ids.len() {
+        if visited[start] || ids[start] as usize == start {
+            continue;
+        }
+        let mut cycle = vec![];
+        let mut pos = start;
+        while !visited[pos] {
+            visited[pos] = true;
+            cycle.push(pos);
+            pos = ids[pos] as usize;
+        }
+        cycles.push(cycle);
+    }
+    cycles
+}
+
+/// For each edge and each corner, the index of the cycle it belongs to, or None if
+/// it is in place. Edge cycles are numbered first, then corner cycles.
+pub fn cycle_groups(cube: &Cube333) -> (Vec<Option<usize>>, Vec<Option<usize>>) {
+    let edge_ids: Vec<u8> = cube.edges.get_edges().iter().map(|e| e.id).collect();
+    let corner_ids: Vec<u8> = cube.corners.get_corners().iter().map(|c| c.id).collect();
+    let mut edges = vec![None; 12];
+    let mut corners = vec![None; 8];
+    let edge_cycles = cycles(&edge_ids);
+    for (i, cycle) in edge_cycles.iter().enumerate() {
+        for pos in cycle {
+            edges[*pos] = Some(i);
+        }
+    }
+    for (i, cycle) in cycles(&corner_ids).iter().enumerate() {
+        for pos in cycle {
+            corners[*pos] = Some(edge_cycles.len() + i);
+        }
+    }
+    (edges, corners)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::finish::Finish;
+    use crate::finish::{cycle_groups, cycles, finish_length, Finish};
     use crate::{Cube, Solvable};
+    use cubelib::cube::Cube333;
 
     #[test]
     fn htr_to_finish() {
@@ -77,4 +298,65 @@ mod tests {
         let solutions = finish.solve(&cube, 2).unwrap();
         assert!(solutions.len() > 0);
     }
+
+    #[test]
+    fn cycle_info_for_corner_3_cycle() {
+        let cube = Cube::new("R' F R' B2 R F' R' B2 R2".to_string()).unwrap().0;
+        let solutions = Finish.solve_cycle_info(&cube, 2).unwrap();
+        assert!(!solutions.is_empty());
+        for (alg, info) in solutions {
+            assert_eq!(info, "solves UBL-UFR-UBR corner cycle");
+            let mut c = Cube(cube);
+            c.apply(&alg);
+            assert!(Finish.is_solved(&c.0));
+        }
+    }
+
+    #[test]
+    fn finish_opportunities() {
+        // R U R' takes the FR pair out of its slot, leaving the rest of the first two
+        // layers solved
+        let cube = Cube::new("R U R'".to_string()).unwrap().0;
+        let hints = Finish.opportunities(&cube);
+        assert!(hints.contains(&"keyhole: FR slot with D layer".to_string()));
+        assert_eq!(hints.iter().filter(|h| h.starts_with("keyhole")).count(), 1);
+
+        let cube = Cube::new("R' F R' B2 R F' R' B2 R2".to_string()).unwrap().0;
+        assert_eq!(
+            Finish.opportunities(&cube),
+            vec!["conjugate: UBL-UFR-UBR corners".to_string()]
+        );
+        assert!(Finish.opportunities(&Cube333::default()).is_empty());
+    }
+
+    #[test]
+    fn test_finish_length() {
+        let length = |s: &str| finish_length(&Cube::new(s.to_string()).unwrap());
+        assert_eq!(length("").unwrap(), 0);
+        assert_eq!(length("R2 U2 R2").unwrap(), 3);
+        let cube = Cube::new("R2 U2 F2 L2 D2 B2 U2".to_string()).unwrap();
+        let optimal = Finish.solve(&cube.0, 1).unwrap()[0].len() as u8;
+        assert_eq!(finish_length(&cube).unwrap(), optimal);
+        assert!(length("R").is_err());
+    }
+
+    #[test]
+    fn test_cycles() {
+        assert_eq!(cycles(&[0, 1, 2, 3]), Vec::<Vec<usize>>::new());
+        assert_eq!(cycles(&[1, 2, 0, 3]), vec![vec![0, 1, 2]]);
+        assert_eq!(cycles(&[1, 0, 3, 2]), vec![vec![0, 1], vec![2, 3]]);
+    }
+
+    #[test]
+    fn test_cycle_groups() {
+        // A corner 3-cycle followed by an edge 3-cycle
+        let cube = Cube::new("R' F R' B2 R F' R' B2 R2 R2 U R U R' U' R' U' R' U R'".to_string())
+            .unwrap()
+            .0;
+        let (edges, corners) = cycle_groups(&cube);
+        assert_eq!(edges.iter().filter(|g| **g == Some(0)).count(), 3);
+        assert_eq!(corners.iter().filter(|g| **g == Some(1)).count(), 3);
+        assert_eq!(edges.iter().filter(|g| g.is_some()).count(), 3);
+        assert_eq!(corners.iter().filter(|g| g.is_some()).count(), 3);
+    }
 }
@@ -1,10 +1,10 @@
-use crate::solver::{solve_step, step_config};
+use crate::solver::{solve_step, step_config, StepFilters};
 use crate::Visibility::{Any, BadFace, BadPiece};
 use crate::{
-    Algorithm, Solvable, CORNER_FB_FACELETS, CORNER_OPPOSITE_E_SLICE, CORNER_OPPOSITE_M_SLICE,
-    CORNER_OPPOSITE_S_SLICE, CORNER_RL_FACELETS, CORNER_UD_FACELETS, EDGE_FB_FACELETS,
-    EDGE_OPPOSITE_E_SLICE, EDGE_OPPOSITE_M_SLICE, EDGE_OPPOSITE_S_SLICE, EDGE_RL_FACELETS,
-    EDGE_UD_FACELETS, HTRFB, HTRRL, HTRUD,
+    Algorithm, CubeView, Solvable, CORNER_FB_FACELETS, CORNER_OPPOSITE_E_SLICE,
+    CORNER_OPPOSITE_M_SLICE, CORNER_OPPOSITE_S_SLICE, CORNER_RL_FACELETS, CORNER_UD_FACELETS,
+    EDGE_FB_FACELETS, EDGE_OPPOSITE_E_SLICE, EDGE_OPPOSITE_M_SLICE, EDGE_OPPOSITE_S_SLICE,
+    EDGE_RL_FACELETS, EDGE_UD_FACELETS, HTRFB, HTRRL, HTRUD,
 };
 use cubelib::cube::turn::TransformableMut;
 use cubelib::cube::{Cube333, Transformation333};
@@ -49,8 +49,8 @@ impl Solvable for FRUD {
         format!("{} {}e", corner_case, bad_edge_count).to_string()
     }
 
-    fn edge_visibility(&self, cube: &Cube333, pos: usize, facelet: u8) -> u8 {
-        let e = cube.edges.get_edges()[pos];
+    fn edge_visibility(&self, cube: &CubeView, pos: usize, facelet: u8) -> u8 {
+        let e = cube.edges[pos];
         let mut v = Any as u8;
         if pos as u8 != EDGE_OPPOSITE_E_SLICE[pos]
             && e.id != pos as u8
@@ -64,10 +64,10 @@ impl Solvable for FRUD {
         v
     }
 
-    fn corner_visibility(&self, cube: &Cube333, pos: usize, facelet: u8) -> u8 {
-        let c = cube.corners.get_corners()[pos];
+    fn corner_visibility(&self, cube: &CubeView, pos: usize, facelet: u8) -> u8 {
+        let c = cube.corners[pos];
         let mut v = Any as u8;
-        let c_opp = cube.corners.get_corners()[CORNER_OPPOSITE_E_SLICE[pos] as usize];
+        let c_opp = cube.corners[CORNER_OPPOSITE_E_SLICE[pos] as usize];
         match c.id {
             2 | 5 => {
                 if c_opp.id != CORNER_OPPOSITE_E_SLICE[c.id as usize] {
@@ -81,12 +81,18 @@ impl Solvable for FRUD {
         }
         v
     }
-    fn solve(&self, cube: &Cube333, count: usize) -> PyResult<Vec<Algorithm>> {
+    fn solve_filtered(
+        &self,
+        cube: &Cube333,
+        count: usize,
+        filters: StepFilters,
+    ) -> PyResult<Vec<Algorithm>> {
         solve_step(
             cube,
             step_config(StepKind::FRLS, "ud", NissSwitchType::Never),
             count,
             false,
+            filters,
         )
     }
 }
@@ -108,8 +114,8 @@ impl Solvable for FRFB {
         FRUD.case_name(&ud_cube)
     }
 
-    fn edge_visibility(&self, cube: &Cube333, pos: usize, facelet: u8) -> u8 {
-        let e = cube.edges.get_edges()[pos];
+    fn edge_visibility(&self, cube: &CubeView, pos: usize, facelet: u8) -> u8 {
+        let e = cube.edges[pos];
         let mut v = Any as u8;
         if pos as u8 != EDGE_OPPOSITE_S_SLICE[pos]
             && e.id != pos as u8
@@ -123,10 +129,10 @@ impl Solvable for FRFB {
         v
     }
 
-    fn corner_visibility(&self, cube: &Cube333, pos: usize, facelet: u8) -> u8 {
-        let c = cube.corners.get_corners()[pos];
+    fn corner_visibility(&self, cube: &CubeView, pos: usize, facelet: u8) -> u8 {
+        let c = cube.corners[pos];
         let mut v = Any as u8;
-        let c_opp = cube.corners.get_corners()[CORNER_OPPOSITE_S_SLICE[pos] as usize];
+        let c_opp = cube.corners[CORNER_OPPOSITE_S_SLICE[pos] as usize];
         match c.id {
             1 | 2 => {
                 if c_opp.id != CORNER_OPPOSITE_S_SLICE[c.id as usize] {
@@ -140,12 +146,18 @@ impl Solvable for FRFB {
         }
         v
     }
-    fn solve(&self, cube: &Cube333, count: usize) -> PyResult<Vec<Algorithm>> {
+    fn solve_filtered(
+        &self,
+        cube: &Cube333,
+        count: usize,
+        filters: StepFilters,
+    ) -> PyResult<Vec<Algorithm>> {
         solve_step(
             cube,
             step_config(StepKind::FRLS, "fb", NissSwitchType::Never),
             count,
             false,
+            filters,
         )
     }
 }
@@ -168,8 +180,8 @@ impl Solvable for FRRL {
         FRUD.case_name(&ud_cube)
     }
 
-    fn edge_visibility(&self, cube: &Cube333, pos: usize, facelet: u8) -> u8 {
-        let e = cube.edges.get_edges()[pos];
+    fn edge_visibility(&self, cube: &CubeView, pos: usize, facelet: u8) -> u8 {
+        let e = cube.edges[pos];
         let mut v = Any as u8;
         if pos as u8 != EDGE_OPPOSITE_M_SLICE[pos]
             && e.id != pos as u8
@@ -183,10 +195,10 @@ impl Solvable for FRRL {
         v
     }
 
-    fn corner_visibility(&self, cube: &Cube333, pos: usize, facelet: u8) -> u8 {
-        let c = cube.corners.get_corners()[pos];
+    fn corner_visibility(&self, cube: &CubeView, pos: usize, facelet: u8) -> u8 {
+        let c = cube.corners[pos];
         let mut v = Any as u8;
-        let c_opp = cube.corners.get_corners()[CORNER_OPPOSITE_M_SLICE[pos] as usize];
+        let c_opp = cube.corners[CORNER_OPPOSITE_M_SLICE[pos] as usize];
         match c.id {
             2 | 3 => {
                 if c_opp.id != CORNER_OPPOSITE_M_SLICE[c.id as usize] {
@@ -200,12 +212,18 @@ impl Solvable for FRRL {
         }
         v
     }
-    fn solve(&self, cube: &Cube333, count: usize) -> PyResult<Vec<Algorithm>> {
+    fn solve_filtered(
+        &self,
+        cube: &Cube333,
+        count: usize,
+        filters: StepFilters,
+    ) -> PyResult<Vec<Algorithm>> {
         solve_step(
             cube,
             step_config(StepKind::FRLS, "lr", NissSwitchType::Never),
             count,
             false,
+            filters,
         )
     }
 }
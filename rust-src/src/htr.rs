@@ -1,15 +1,17 @@
-use crate::solver::{solve_step_deduplicated, step_config};
+use crate::solver::{solve_step_deduplicated, step_config, FilterLastMove, StepFilters};
 use crate::Visibility::{Any, BadFace, BadPiece, HtrD, TopColor};
 use crate::{
-    Algorithm, DrawableCorner, Solvable, CORNER_FB_FACELETS, CORNER_RL_FACELETS,
+    Algorithm, CubeView, DrawableCorner, Solvable, CORNER_FB_FACELETS, CORNER_RL_FACELETS,
     CORNER_UD_FACELETS, EDGE_FB_FACELETS, EDGE_RL_FACELETS, EDGE_UD_FACELETS,
 };
 use cubelib::algs::Algorithm as LibAlgorithm;
 use cubelib::cube::turn::{ApplyAlgorithm, TransformableMut};
-use cubelib::cube::{Cube333, Transformation333};
+use cubelib::cube::{Cube333, Transformation333, Turn333};
 use cubelib::defs::{NissSwitchType, StepKind};
+use cubelib::solver_new::group::StepPredicate;
 use cubelib::steps::coord::Coord;
 use cubelib::steps::fr::coords::FRUDNoSliceCoord;
+use pyo3::exceptions::PyValueError;
 use pyo3::PyResult;
 
 pub struct HTRUD;
@@ -32,8 +34,8 @@ impl Solvable for HTRUD {
             _ => "".to_string(),
         }
     }
-    fn edge_visibility(&self, cube: &Cube333, pos: usize, facelet: u8) -> u8 {
-        let e = cube.edges.get_edges()[pos];
+    fn edge_visibility(&self, cube: &CubeView, pos: usize, facelet: u8) -> u8 {
+        let e = cube.edges[pos];
         if !e.oriented_ud {
             let mut v = BadPiece as u8;
             if Some(facelet) != EDGE_UD_FACELETS[pos] {
@@ -44,8 +46,8 @@ impl Solvable for HTRUD {
             Any as u8
         }
     }
-    fn corner_visibility(&self, cube: &Cube333, pos: usize, facelet: u8) -> u8 {
-        let c = cube.corners.get_corners()[pos];
+    fn corner_visibility(&self, cube: &CubeView, pos: usize, facelet: u8) -> u8 {
+        let c = cube.corners[pos];
         let mut v = Any as u8;
         if facelet == c.facelet_showing_ud() {
             // D sticker
@@ -63,16 +65,93 @@ impl Solvable for HTRUD {
         }
         v
     }
-    fn solve(&self, cube: &Cube333, count: usize) -> PyResult<Vec<Algorithm>> {
-        solve_step_deduplicated(
-            cube,
-            step_config(StepKind::HTR, "", NissSwitchType::Never),
-            count,
-            true,
-            is_equivalent(Transformation333::Y),
-        )
+    fn solve_filtered(
+        &self,
+        cube: &Cube333,
+        count: usize,
+        filters: StepFilters,
+    ) -> PyResult<Vec<Algorithm>> {
+        solve_htr(cube, "ud", count, filters)
     }
 }
+impl HTRUD {
+    /// Names the corner arrangement on the U and D layers: "solved" (up to AUF),
+    /// "bars" (some adjacent pair of corners is solved relative to each other),
+    /// "diagonal" (only diagonal pairs are) or "none".
+    pub fn corner_pattern(&self, cube: &Cube333) -> String {
+        let corners = cube.corners.get_corners();
+        let relatively_solved = |p: usize, q: usize| {
+            let layer = p / 4;
+            let (a, b) = (corners[p], corners[q]);
+            a.id as usize / 4 == layer
+                && b.id as usize / 4 == layer
+                && (a.id as usize + 4 - p % 4) % 4 == (b.id as usize + 4 - q % 4) % 4
+        };
+        let bars: Vec<usize> = (0..2)
+            .map(|layer| {
+                (0..4)
+                    .filter(|i| relatively_solved(4 * layer + i, 4 * layer + (i + 1) % 4))
+                    .count()
+            })
+            .collect();
+        let diagonals = (0..2)
+            .flat_map(|layer| (0..2).map(move |i| (4 * layer + i, 4 * layer + i + 2)))
+            .any(|(p, q)| relatively_solved(p, q));
+        if bars == vec![4, 4] {
+            "solved".to_string()
+        } else if bars.iter().sum::<usize>() > 0 {
+            "bars".to_string()
+        } else if diagonals {
+            "diagonal".to_string()
+        } else {
+            "none".to_string()
+        }
+    }
+}
+
+/// Solves HTR, deduplicating results by the FR state seen with the given axis as
+/// the domino axis.
+pub fn solve_htr(
+    cube: &Cube333,
+    axis: &str,
+    count: usize,
+    filters: StepFilters,
+) -> PyResult<Vec<Algorithm>> {
+    let transform = match axis {
+        "ud" => Transformation333::Y,
+        "fb" => Transformation333::X,
+        "rl" => Transformation333::Z,
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "Unknown variant '{}' for htr",
+                axis
+            )))
+        }
+    };
+    solve_step_deduplicated(
+        cube,
+        step_config(StepKind::HTR, "", NissSwitchType::Never),
+        count,
+        true,
+        is_equivalent(transform),
+        filters,
+    )
+}
+
+/// Only accepts HTRs ending in a half turn of a face on the given axis
+pub fn last_half_turn_filter(axis: &str) -> PyResult<Box<dyn StepPredicate>> {
+    let faces = match axis {
+        "ud" => "UD",
+        "fb" => "FB",
+        "rl" => "RL",
+        _ => return Err(PyValueError::new_err(format!("Unknown axis '{}'", axis))),
+    };
+    Ok(FilterLastMove::new(move |t: &Turn333| {
+        let s = format!("{}", t);
+        s.ends_with('2') && faces.contains(&s[..1])
+    }))
+}
+
 fn is_equivalent(transform: Transformation333) -> impl Fn(&Cube333, &LibAlgorithm) -> usize {
     move |cube: &Cube333, _alg: &LibAlgorithm| {
         let mut cube = cube.clone();
@@ -104,8 +183,8 @@ impl Solvable for HTRFB {
             _ => "".to_string(),
         }
     }
-    fn edge_visibility(&self, cube: &Cube333, pos: usize, facelet: u8) -> u8 {
-        let e = cube.edges.get_edges()[pos];
+    fn edge_visibility(&self, cube: &CubeView, pos: usize, facelet: u8) -> u8 {
+        let e = cube.edges[pos];
         let mut v = Any as u8;
         if !e.oriented_fb {
             v |= BadPiece as u8;
@@ -115,8 +194,8 @@ impl Solvable for HTRFB {
         }
         v
     }
-    fn corner_visibility(&self, cube: &Cube333, pos: usize, facelet: u8) -> u8 {
-        let c = cube.corners.get_corners()[pos];
+    fn corner_visibility(&self, cube: &CubeView, pos: usize, facelet: u8) -> u8 {
+        let c = cube.corners[pos];
         let mut v = Any as u8;
         if facelet == c.facelet_showing_fb() {
             if vec![0, 1, 6, 7].contains(&c.id) {
@@ -133,14 +212,13 @@ impl Solvable for HTRFB {
         }
         v
     }
-    fn solve(&self, cube: &Cube333, count: usize) -> PyResult<Vec<Algorithm>> {
-        solve_step_deduplicated(
-            cube,
-            step_config(StepKind::HTR, "", NissSwitchType::Never),
-            count,
-            true,
-            is_equivalent(Transformation333::X),
-        )
+    fn solve_filtered(
+        &self,
+        cube: &Cube333,
+        count: usize,
+        filters: StepFilters,
+    ) -> PyResult<Vec<Algorithm>> {
+        solve_htr(cube, "fb", count, filters)
     }
 }
 pub struct HTRRL;
@@ -154,8 +232,8 @@ impl Solvable for HTRRL {
     fn case_name(&self, cube: &Cube333) -> String {
         HTRUD.case_name(cube)
     }
-    fn edge_visibility(&self, cube: &Cube333, pos: usize, facelet: u8) -> u8 {
-        let e = cube.edges.get_edges()[pos];
+    fn edge_visibility(&self, cube: &CubeView, pos: usize, facelet: u8) -> u8 {
+        let e = cube.edges[pos];
         let mut v = Any as u8;
         if !e.oriented_rl {
             v |= BadPiece as u8;
@@ -165,8 +243,8 @@ impl Solvable for HTRRL {
         }
         v
     }
-    fn corner_visibility(&self, cube: &Cube333, pos: usize, facelet: u8) -> u8 {
-        let c = cube.corners.get_corners()[pos];
+    fn corner_visibility(&self, cube: &CubeView, pos: usize, facelet: u8) -> u8 {
+        let c = cube.corners[pos];
         let mut v = Any as u8;
         if facelet == c.facelet_showing_rl() {
             if vec![1, 2, 5, 6].contains(&c.id) {
@@ -183,14 +261,13 @@ impl Solvable for HTRRL {
         }
         v
     }
-    fn solve(&self, cube: &Cube333, count: usize) -> PyResult<Vec<Algorithm>> {
-        solve_step_deduplicated(
-            cube,
-            step_config(StepKind::HTR, "", NissSwitchType::Never),
-            count,
-            true,
-            is_equivalent(Transformation333::Z),
-        )
+    fn solve_filtered(
+        &self,
+        cube: &Cube333,
+        count: usize,
+        filters: StepFilters,
+    ) -> PyResult<Vec<Algorithm>> {
+        solve_htr(cube, "rl", count, filters)
     }
 }
 
@@ -224,6 +301,52 @@ mod tests {
         assert_ne!(algs.len(), 0);
     }
 
+    #[test]
+    fn test_htr_axis() {
+        let scramble = "R U2 F2 U2 R D2 F2";
+        let mut cube = Cube333::default();
+        cube.apply_alg(&LibAlgorithm::from_str(scramble).unwrap());
+        let ud = solve_htr(&cube, "ud", 5, StepFilters::default()).unwrap();
+        let fb = solve_htr(&cube, "fb", 5, StepFilters::default()).unwrap();
+        assert!(!ud.is_empty());
+        assert!(!fb.is_empty());
+        for alg in ud.iter().chain(fb.iter()) {
+            let mut c = cube.clone();
+            c.apply_alg(&alg.0);
+            assert!(HTRUD.is_solved(&c));
+        }
+        assert!(solve_htr(&cube, "xy", 5, StepFilters::default()).is_err());
+    }
+
+    #[test]
+    fn test_htr_last_half_turn() {
+        let scramble = "R U2 F2 U2 R D2 F2";
+        let mut cube = Cube333::default();
+        cube.apply_alg(&LibAlgorithm::from_str(scramble).unwrap());
+        let filters = vec![last_half_turn_filter("rl").unwrap()];
+        let solutions = HTRUD.solve_filtered(&cube, 5, filters.into()).unwrap();
+        assert!(!solutions.is_empty());
+        for alg in solutions {
+            let last = format!("{}", alg.0.normal_moves.last().unwrap());
+            assert!(last == "R2" || last == "L2");
+        }
+        assert!(last_half_turn_filter("xy").is_err());
+    }
+
+    #[test]
+    fn test_corner_pattern() {
+        let pattern = |scramble: &str| {
+            let mut cube = Cube333::default();
+            cube.apply_alg(&LibAlgorithm::from_str(scramble).unwrap());
+            HTRUD.corner_pattern(&cube)
+        };
+        assert_eq!(pattern(""), "solved");
+        assert_eq!(pattern("U"), "solved");
+        assert_eq!(pattern("R2"), "bars");
+        assert_eq!(pattern("R2 U2 F2"), "diagonal");
+        assert_eq!(pattern("R2 F2"), "none");
+    }
+
     #[test]
     fn test_htr() {
         let scramble = "R U2 F2 U2 R";
@@ -1,7 +1,13 @@
-use crate::finish::Finish;
-use crate::{Algorithm, Solvable};
-use cubelib::cube::Cube333;
-use pyo3::PyResult;
+use crate::finish::{cycles, Finish, FACE_TURNS};
+use crate::solver::StepFilters;
+use crate::{apply_insertion, pieces_solved, Algorithm, CubeView, Solvable};
+use cubelib::algs::Algorithm as LibAlgorithm;
+use cubelib::cube::turn::ApplyAlgorithm;
+use cubelib::cube::{Cube333, Turn333};
+use pyo3::exceptions::PyValueError;
+use pyo3::{pyfunction, PyResult};
+use std::collections::HashMap;
+use std::str::FromStr;
 
 pub struct Insertions;
 impl Solvable for Insertions {
@@ -20,16 +26,207 @@ impl Solvable for Insertions {
         }
     }
 
-    fn edge_visibility(&self, cube: &Cube333, pos: usize, _facelet: u8) -> u8 {
+    fn edge_visibility(&self, cube: &CubeView, pos: usize, _facelet: u8) -> u8 {
         Finish.edge_visibility(cube, pos, _facelet)
     }
 
-    fn corner_visibility(&self, cube: &Cube333, pos: usize, _facelet: u8) -> u8 {
+    fn corner_visibility(&self, cube: &CubeView, pos: usize, _facelet: u8) -> u8 {
         Finish.corner_visibility(cube, pos, _facelet)
     }
-    fn solve(&self, _cube: &Cube333, _count: usize) -> PyResult<Vec<Algorithm>> {
+    fn solve_filtered(
+        &self,
+        _cube: &Cube333,
+        _count: usize,
+        _filters: StepFilters,
+    ) -> PyResult<Vec<Algorithm>> {
         Err(pyo3::exceptions::PyValueError::new_err(
             "No solver for insertions",
         ))
     }
 }
+
+// Longest insertion `best_insertion` searches for. The search meets in the middle, so
+// its table holds every state up to half this many moves.
+const MAX_INSERTION_LEN: usize = 10;
+
+/// The insertion that solves the residual of `skeleton` with the smallest change in
+/// move count, searching every insertion of up to `max_insertion_len` moves (at most
+/// 10) at every point. The residual is the state the flattened skeleton leaves on a
+/// solved cube, and must be a single 3-cycle of corners or of edges. Returns the
+/// insertion, the position in the flattened skeleton to insert it before, and the
+/// change in move count after cancellation.
+#[pyfunction]
+pub fn best_insertion(
+    skeleton: &Algorithm,
+    max_insertion_len: usize,
+) -> PyResult<(Algorithm, usize, i32)> {
+    if max_insertion_len > MAX_INSERTION_LEN {
+        return Err(PyValueError::new_err(format!(
+            "Insertions are limited to {} moves",
+            MAX_INSERTION_LEN
+        )));
+    }
+    let turns: Vec<LibAlgorithm> = FACE_TURNS
+        .iter()
+        .map(|t| LibAlgorithm::from_str(t).unwrap())
+        .collect();
+    let moves = skeleton.0.to_uninverted().normal_moves;
+    let part = |moves: &[Turn333]| LibAlgorithm {
+        normal_moves: moves.to_vec(),
+        inverse_moves: vec![],
+    };
+    let mut residual = Cube333::default();
+    residual.apply_alg(&part(&moves));
+    if !is_three_cycle(&residual) {
+        return Err(PyValueError::new_err(
+            "The skeleton must leave a single 3-cycle of corners or of edges",
+        ));
+    }
+
+    // Insertion A at k solves the skeleton T1 T2 when T1 A T2 is solved, so A is
+    // (T2 T1)^-1, the inverse of the residual conjugated by T2. Writing A = P Q, the
+    // state of Q^-1 = T2 T1 P is looked up in a table of short sequences R, and then
+    // Q = R^-1.
+    let table_depth = max_insertion_len - max_insertion_len / 2;
+    let mut table: HashMap<(u64, u64, u64), Vec<usize>> = HashMap::new();
+    for_each_sequence(&Cube333::default(), table_depth, &turns, &mut |r, c| {
+        let known = table.get(&key(c)).map_or(false, |q| q.len() <= r.len());
+        if !known {
+            table.insert(key(c), r.iter().rev().map(|i| inverse_turn(*i)).collect());
+        }
+    });
+
+    let mut best: Option<(Algorithm, usize, i32)> = None;
+    for k in 0..=moves.len() {
+        let mut start = Cube333::default();
+        start.apply_alg(&part(&moves[k..]));
+        start.apply_alg(&part(&moves[..k]));
+        let mut found = vec![];
+        for_each_sequence(&start, max_insertion_len / 2, &turns, &mut |p, c| {
+            if let Some(q) = table.get(&key(c)) {
+                found.push(
+                    p.iter()
+                        .chain(q.iter())
+                        .map(|i| FACE_TURNS[*i])
+                        .collect::<Vec<_>>(),
+                );
+            }
+        });
+        for insertion in found {
+            let insertion = Algorithm::new(&insertion.join(" "))?;
+            let insertion = Algorithm::new("")?.merge(&insertion);
+            if insertion.is_empty() || insertion.len() > max_insertion_len {
+                continue;
+            }
+            let (_, change) = apply_insertion(skeleton, &insertion, k)?;
+            let better = best.as_ref().map_or(true, |(alg, _, c)| {
+                (change, insertion.len()) < (*c, alg.len())
+            });
+            if better {
+                best = Some((insertion, k, change));
+            }
+        }
+    }
+    best.ok_or_else(|| {
+        PyValueError::new_err(format!(
+            "No insertion of at most {} moves finishes the skeleton",
+            max_insertion_len
+        ))
+    })
+}
+
+// True if the state is a single 3-cycle of corners or of edges, with every other piece
+// solved. The cycled pieces may be twisted or flipped.
+fn is_three_cycle(cube: &Cube333) -> bool {
+    let edge_ids: Vec<u8> = cube.edges.get_edges().iter().map(|e| e.id).collect();
+    let corner_ids: Vec<u8> = cube.corners.get_corners().iter().map(|c| c.id).collect();
+    let others = |n: usize, cycle: &[usize]| -> Vec<usize> {
+        (0..n).filter(|pos| !cycle.contains(pos)).collect()
+    };
+    match (cycles(&edge_ids).as_slice(), cycles(&corner_ids).as_slice()) {
+        ([cycle], []) if cycle.len() == 3 => {
+            pieces_solved(cube, &others(12, cycle), &others(8, &[]))
+        }
+        ([], [cycle]) if cycle.len() == 3 => {
+            pieces_solved(cube, &others(12, &[]), &others(8, cycle))
+        }
+        _ => false,
+    }
+}
+
+fn key(cube: &Cube333) -> (u64, u64, u64) {
+    let edges = cube.edges.get_edges_raw();
+    (edges[0], edges[1], cube.corners.get_corners_raw())
+}
+
+// Index in FACE_TURNS of the turn undoing turn `i`
+fn inverse_turn(i: usize) -> usize {
+    i - i % 3 + 2 - i % 3
+}
+
+// Calls `visit` with every sequence of up to `depth` turns (as indices into
+// FACE_TURNS) and the cube it leads to from `start`. A face never follows itself or,
+// for opposite faces, the later one in FACE_TURNS, so each sequence is visited once.
+fn for_each_sequence<F>(start: &Cube333, depth: usize, turns: &[LibAlgorithm], visit: &mut F)
+where
+    F: FnMut(&[usize], &Cube333),
+{
+    fn walk<F>(
+        cube: &Cube333,
+        seq: &mut Vec<usize>,
+        depth: usize,
+        turns: &[LibAlgorithm],
+        visit: &mut F,
+    ) where
+        F: FnMut(&[usize], &Cube333),
+    {
+        visit(seq, cube);
+        if seq.len() == depth {
+            return;
+        }
+        for i in 0..turns.len() {
+            if let Some(last) = seq.last() {
+                let (face, last_face) = (i / 3, last / 3);
+                if face == last_face || (face / 2 == last_face / 2 && face < last_face) {
+                    continue;
+                }
+            }
+            let mut next = *cube;
+            next.apply_alg(&turns[i]);
+            seq.push(i);
+            walk(&next, seq, depth, turns, visit);
+            seq.pop();
+        }
+    }
+    walk(start, &mut vec![], depth, turns, visit);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::finish::is_fully_solved;
+    use crate::insertions::best_insertion;
+    use crate::{apply_insertion, Algorithm, Cube};
+
+    #[test]
+    fn insertion_for_corner_3_cycle() {
+        // The skeleton leaves an A-perm corner 3-cycle, conjugated by D
+        let skeleton = Algorithm::new("D R' F R' B2 R F' R' B2 R2 D'").unwrap();
+        let (insertion, at, change) = best_insertion(&skeleton, 9).unwrap();
+        let (result, expected_change) = apply_insertion(&skeleton, &insertion, at).unwrap();
+        assert_eq!(change, expected_change);
+        // Inserting the inverse A-perm after D cancels the whole skeleton
+        assert_eq!(change, -11);
+        let mut c = Cube::new("".to_string()).unwrap();
+        c.apply(&result);
+        assert!(is_fully_solved(&c.0));
+        assert!(best_insertion(&skeleton, 11).is_err());
+    }
+
+    #[test]
+    fn insertion_needs_3_cycle() {
+        assert!(best_insertion(&Algorithm::new("R U").unwrap(), 8).is_err());
+        // An A-perm followed by U2 moves edges as well as corners
+        let skeleton = Algorithm::new("R' F R' B2 R F' R' B2 R2 U2").unwrap();
+        assert!(best_insertion(&skeleton, 8).is_err());
+    }
+}
@@ -1,30 +1,60 @@
+mod cubedb;
 mod dr;
 mod eo;
 mod finish;
 mod fr;
 mod htr;
 mod insertions;
+mod orientation;
+mod pruning;
+mod render;
+mod session;
 mod slice;
 mod solver;
 
+use pyo3::basic::CompareOp;
 use pyo3::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 use std::str::FromStr;
 
-use pyo3::exceptions::PyValueError;
+use pyo3::exceptions::{PyIndexError, PyRuntimeError, PyValueError};
 
-use crate::dr::{DRFB, DRRL, DRUD};
-use crate::eo::{EOFB, EORL, EOUD};
-use crate::finish::Finish;
+use crate::cubedb::{from_cubedb, to_cubedb};
+use crate::dr::{
+    available_dr_axes, corner_count_filter, eodr_overlay, subset_cap, DRFB, DRRL, DRUD,
+};
+use crate::eo::{eo_both_axes, min_eo, EOFB, EORL, EOUD};
+use crate::finish::{
+    cycle_groups, cycles, distance_within, finish_length, is_fully_solved, Finish, FACE_TURNS,
+};
 use crate::fr::{FRFB, FRRL, FRUD};
-use crate::htr::{HTRFB, HTRRL, HTRUD};
-use crate::insertions::Insertions;
+use crate::htr::{last_half_turn_filter, solve_htr, HTRFB, HTRRL, HTRUD};
+use crate::insertions::{best_insertion, Insertions};
+use crate::orientation::{normalize, Move, Notation, Orientation};
+use crate::pruning::lower_bound;
+use crate::render::{color_at, from_facelets, from_scan, kociemba_string, render_data, Sticker};
+use crate::session::{next_steps, Session};
 use crate::slice::{SliceFB, SliceRL, SliceUD};
-use crate::solver::{group, parse_steps, scramble};
+use crate::solver::{
+    canonical_scramble, group, invert_scramble, normalize_scramble, parse_steps, scramble,
+    scramble_cube, scramble_seeded_in_range, solve_pipeline, solve_step, states_along,
+    symmetry_dedup_cap, transform_solution_frame, CaseCap, FilterEveryState, FilterLinear,
+    StepFilters,
+};
 use crate::Visibility::Any;
 use cubelib::algs::Algorithm as LibAlgorithm;
-use cubelib::cube::turn::{ApplyAlgorithm, Direction, Invertible, InvertibleMut};
-use cubelib::cube::{Corner, Cube333, Turn333};
+use cubelib::cube::turn::{ApplyAlgorithm, Direction, Invertible, InvertibleMut, TransformableMut};
+use cubelib::cube::{
+    Corner, CornerCube333, Cube333, Edge, EdgeCube333, Transformation333, Turn333,
+};
 use cubelib::defs::StepKind;
+use cubelib::solver::solution::Solution as LibSolution;
+use cubelib::solver_new::util_steps::FilterLastMoveNotPrime;
+use cubelib::steps::step::StepConfig;
 
 #[pyclass]
 struct Solution {
@@ -34,51 +64,238 @@ struct Solution {
     algs: Vec<Algorithm>,
 }
 
+impl Solution {
+    fn from_lib(sol: &LibSolution) -> Self {
+        let mut py_steps = vec![];
+        let mut py_algs = vec![];
+        for step in sol.get_steps() {
+            let variant = match step.kind {
+                StepKind::EO | StepKind::HTR | StepKind::FR => &step.variant,
+                StepKind::DR => &step.variant[0..2],
+                _ => "",
+            };
+            let variant = match variant {
+                "lr" => "rl".to_string(),
+                other => other.to_string(),
+            };
+            py_steps.push(StepInfo {
+                kind: step.kind.to_string(),
+                variant: variant,
+            });
+            py_algs.push(Algorithm::from(step.alg.clone()));
+        }
+        Solution {
+            steps: py_steps,
+            algs: py_algs,
+        }
+    }
+}
+
+/// Complete solutions of a multi-step plan, found one at a time as Python iterates
+#[pyclass(unsendable)]
+struct SolutionIter(Box<dyn Iterator<Item = LibSolution>>);
+
+#[pymethods]
+impl SolutionIter {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> Option<Solution> {
+        self.0.next().map(|sol| Solution::from_lib(&sol))
+    }
+}
+
+/// Solves `steps` (e.g. "EO > DR[ud]") from the scrambled state, lazily
+#[pyfunction]
+fn solve_plan_iter(scramble: &str, steps: &str) -> PyResult<SolutionIter> {
+    let cube = Cube::new(scramble.to_string())?;
+    let step_configs = parse_steps(steps).map_err(|s| PyValueError::new_err(s))?;
+    let mut steps =
+        group(StepKind::from_str("")?, &step_configs).map_err(|s| PyValueError::new_err(s))?;
+    steps.apply_step_limit(100);
+    Ok(SolutionIter(Box::new(steps.into_worker(cube.0))))
+}
+
+/// Solves `n` random cubes, drawn from an RNG seeded with `seed`, with the plan
+/// `steps` and summarizes the length of each step as JSON, e.g.
+/// {"n": 10, "steps": {"eo": {"mean": 4.30, "median": 4.00, "min": 3, "max": 6}}}
+#[pyfunction]
+fn solve_stats(n: usize, steps: &str, seed: u64) -> PyResult<String> {
+    if n == 0 {
+        return Err(PyValueError::new_err("n must be at least 1"));
+    }
+    let step_configs = parse_steps(steps).map_err(|s| PyValueError::new_err(s))?;
+    let mut rng = StdRng::seed_from_u64(seed);
+    // Step lengths, keyed by step kind in plan order
+    let mut lengths: Vec<(String, Vec<usize>)> = vec![];
+    for _ in 0..n {
+        let cube = Cube333::random(&mut rng);
+        let mut group =
+            group(StepKind::from_str("")?, &step_configs).map_err(|s| PyValueError::new_err(s))?;
+        group.apply_step_limit(100);
+        let solution = group
+            .into_worker(cube)
+            .next()
+            .ok_or_else(|| PyValueError::new_err("No solution found"))?;
+        let solution = Solution::from_lib(&solution);
+        for (step, alg) in solution.steps.iter().zip(solution.algs.iter()) {
+            let kind = step.kind.to_lowercase();
+            match lengths.iter_mut().find(|(k, _)| *k == kind) {
+                Some((_, lens)) => lens.push(alg.len()),
+                None => lengths.push((kind, vec![alg.len()])),
+            }
+        }
+    }
+    let summaries: Vec<String> = lengths
+        .iter_mut()
+        .map(|(kind, lens)| {
+            lens.sort();
+            let mid = lens.len() / 2;
+            let median = if lens.len() % 2 == 0 {
+                (lens[mid - 1] + lens[mid]) as f64 / 2.0
+            } else {
+                lens[mid] as f64
+            };
+            let mean = lens.iter().sum::<usize>() as f64 / lens.len() as f64;
+            format!(
+                "\"{}\": {{\"mean\": {:.2}, \"median\": {:.2}, \"min\": {}, \"max\": {}}}",
+                kind,
+                mean,
+                median,
+                lens[0],
+                lens[lens.len() - 1]
+            )
+        })
+        .collect();
+    Ok(format!(
+        "{{\"n\": {}, \"steps\": {{{}}}}}",
+        n,
+        summaries.join(", ")
+    ))
+}
+
+// The face turns relative to the centers, the comment, and the moves as written when
+// they include wide moves, slice moves or rotations
 #[derive(Clone)]
 #[pyclass]
-struct Algorithm(LibAlgorithm);
+struct Algorithm(LibAlgorithm, Option<String>, Option<Notation>);
+
+impl From<LibAlgorithm> for Algorithm {
+    fn from(alg: LibAlgorithm) -> Self {
+        Algorithm(alg, None, None)
+    }
+}
 
 #[pymethods]
 impl Algorithm {
+    /// Face turns, wide moves, slice moves and rotations, with the inverse moves in
+    /// parentheses. Anything but face turns is kept as written, with repeated layers
+    /// cancelled: "M M" is "M2" and "M M'" is empty.
     #[new]
     fn new(s: &str) -> PyResult<Self> {
-        let alg = LibAlgorithm::from_str(s)
+        if let Ok(alg) = LibAlgorithm::from_str(s) {
+            return Ok(Algorithm::from(alg));
+        }
+        let moves = Notation::from_str(s)
             .map_err(|_| PyValueError::new_err(format!("Invalid algorithm: {}", s)))?;
-        Ok(Algorithm(alg))
+        Ok(Algorithm::from_notation(moves, None))
+    }
+
+    /// Builds an algorithm from a list of moves, all on the normal side or all on the
+    /// inverse, cancelling as `append` does
+    #[staticmethod]
+    #[pyo3(signature = (moves, inverse=false))]
+    fn from_moves(moves: Vec<String>, inverse: bool) -> PyResult<Algorithm> {
+        moves
+            .iter()
+            .try_fold(Algorithm::new("")?, |alg, m| alg.append(m, inverse))
     }
 
     fn normal_moves(&self) -> Vec<String> {
-        self.0
-            .normal_moves
+        self.notation()
+            .normal
             .iter()
-            .map(|t| format!("{}", t))
+            .map(|m| m.to_string())
             .collect()
     }
 
     fn inverse_moves(&self) -> Vec<String> {
-        self.0
-            .inverse_moves
+        self.notation()
+            .inverse
             .iter()
-            .map(|t| format!("{}", t))
+            .map(|m| m.to_string())
             .collect()
     }
 
     fn is_empty(&self) -> bool {
-        self.0.normal_moves.is_empty() && self.0.inverse_moves.is_empty()
+        let moves = self.notation();
+        moves.normal.is_empty() && moves.inverse.is_empty()
     }
 
+    /// Number of face turns relative to the centers, so "M" counts as two and "x" as none
     fn len(&self) -> usize {
         self.0.normal_moves.len() + self.0.inverse_moves.len()
     }
 
+    /// Move count in "htm", "qtm", "stm" or "etm", counting both sides and each move as
+    /// written. A slice move is two face turns in HTM and QTM but one move in STM and
+    /// ETM, and rotations only count in ETM.
+    fn metric(&self, kind: &str) -> PyResult<usize> {
+        let metric = kind.to_lowercase();
+        if !["htm", "qtm", "stm", "etm"].contains(&metric.as_str()) {
+            return Err(PyValueError::new_err(format!("Unknown metric: {}", kind)));
+        }
+        let cost = |m: &Move| {
+            let turns = if metric == "qtm" && m.quarters == 2 {
+                2
+            } else {
+                1
+            };
+            match (metric.as_str(), m.is_rotation(), m.is_slice()) {
+                ("etm", _, _) => 1,
+                (_, true, _) => 0,
+                ("htm" | "qtm", _, true) => 2 * turns,
+                _ => turns,
+            }
+        };
+        let moves = self.notation();
+        Ok(moves
+            .normal
+            .iter()
+            .chain(moves.inverse.iter())
+            .map(cost)
+            .sum())
+    }
+
+    /// Appends a single move: a face turn, wide move, slice move or rotation. It cancels
+    /// against the last move on the same layer, looking past moves around the same axis,
+    /// so "M" then "R" then "M'" is "R".
     fn append(&self, s: &str, inverse: bool) -> PyResult<Algorithm> {
-        let turn = Turn333::from_str(s)
-            .map_err(|_| PyValueError::new_err(format!("Invalid move: {}", s)))?;
-        let alg = append_move(&self.0, turn, inverse);
-        Ok(Algorithm(alg))
+        let m =
+            Move::from_str(s).map_err(|_| PyValueError::new_err(format!("Invalid move: {}", s)))?;
+        if self.2.is_none() && m.is_face_turn() {
+            let turn = Turn333::from_str(&m.to_string()).unwrap();
+            let alg = append_move(&self.0, turn, inverse);
+            return Ok(Algorithm(alg, self.1.clone(), None));
+        }
+        let mut moves = self.notation();
+        moves.push(m, inverse);
+        Ok(Algorithm::from_notation(moves, self.1.clone()))
     }
 
     fn merge(&self, other: &Algorithm) -> Algorithm {
+        if self.2.is_some() || other.2.is_some() {
+            let mut moves = self.notation();
+            let other_moves = other.notation();
+            for m in other_moves.normal {
+                moves.push(m, false);
+            }
+            for m in other_moves.inverse {
+                moves.push(m, true);
+            }
+            return Algorithm::from_notation(moves, self.1.clone());
+        }
         let mut alg = self.0.clone();
         for turn in other.0.normal_moves.iter() {
             alg = append_move(&alg, *turn, false);
@@ -86,13 +303,44 @@ impl Algorithm {
         for turn in other.0.inverse_moves.iter() {
             alg = append_move(&alg, *turn, true);
         }
-        Algorithm(alg)
+        Algorithm(alg, self.1.clone(), None)
+    }
+
+    /// Number of moves that cancel when `other` is appended with `merge`
+    fn cancellation_count(&self, other: &Algorithm) -> usize {
+        count_cancellations(self, other)
+    }
+
+    /// Appends `other`, keeping each move on the side it was found on. If `other` was
+    /// found by solving the inverse of the cube, its normal moves belong on the inverse
+    /// side of this solution and vice versa, so pass `flip=true` to swap its sides
+    /// before merging.
+    #[pyo3(signature = (other, flip=false))]
+    fn merge_niss(&self, other: &Algorithm, flip: bool) -> Algorithm {
+        if flip {
+            self.merge(&other.on_inverse())
+        } else {
+            self.merge(other)
+        }
     }
 
+    /// Each side undone, as written. Slice and wide moves turn the centers, so "M U" is
+    /// undone by "U' M'", whose face turns are not those of "M U" reversed.
     fn inverted(&self) -> Algorithm {
-        let mut alg = self.0.clone();
-        alg.invert();
-        Algorithm(alg)
+        match &self.2 {
+            Some(moves) => Algorithm::from_notation(moves.inverted(), None),
+            None => {
+                let mut alg = self.0.clone();
+                alg.invert();
+                Algorithm::from(alg)
+            }
+        }
+    }
+
+    /// A scramble for the state this solution solves: the inverse of the flattened
+    /// solution, with no moves on the inverse
+    fn to_reverse_solution(&self) -> Algorithm {
+        self.all_on_normal().inverted()
     }
 
     fn on_inverse(&self) -> Algorithm {
@@ -101,19 +349,774 @@ impl Algorithm {
             normal_moves: alg.inverse_moves,
             inverse_moves: alg.normal_moves,
         };
-        Algorithm(alg)
+        let moves = self.2.clone().map(|moves| Notation {
+            normal: moves.inverse,
+            inverse: moves.normal,
+        });
+        Algorithm(alg, None, moves)
     }
 
+    /// The flattened solution as face turns relative to the centers
     fn all_on_normal(&self) -> Algorithm {
         let alg = self.0.clone();
         Algorithm::new("")
             .unwrap()
-            .merge(&Algorithm(alg.to_uninverted()))
+            .merge(&Algorithm::from(alg.to_uninverted()))
+    }
+
+    /// Rewrites moves written as seen in the `from` view (e.g. "uf") so that they
+    /// do the same thing when read in the `to` view.
+    fn rotate_solution_to_frame(&self, from: &str, to: &str) -> PyResult<Algorithm> {
+        let from = Orientation::from_str(from).map_err(|e| PyValueError::new_err(e))?;
+        let to = Orientation::from_str(to).map_err(|e| PyValueError::new_err(e))?;
+        let moves = self
+            .notation()
+            .relabeled(|face| to.relative(from.absolute(face)));
+        Ok(Algorithm::from_notation(moves, None))
+    }
+
+    /// The same moves as seen with `new_front` in front and `new_top` on top, e.g.
+    /// reorient("R", "U") turns "R U" into "F U"
+    fn reorient(&self, new_front: &str, new_top: &str) -> PyResult<Algorithm> {
+        self.rotate_solution_to_frame("uf", &format!("{}{}", new_top, new_front))
+    }
+
+    /// The same moves as named after a cube rotation such as "x", "y2" or "z'"
+    fn transform(&self, rotation: &str) -> PyResult<Algorithm> {
+        let (axis, times) = parse_rotation(rotation)?;
+        let view = (0..times).fold(Orientation::from_str("uf").unwrap(), |view, _| {
+            view.rotated(axis)
+        });
+        let moves = self.notation().relabeled(|face| view.relative(face));
+        Ok(Algorithm::from_notation(moves, None))
+    }
+
+    /// Half-turn count of the flattened solution, including cancellations across the
+    /// NISS switch that `len()` does not see.
+    fn htm_with_niss(&self) -> usize {
+        self.all_on_normal().len()
+    }
+
+    /// The same moves reflected through the M, S or E slice
+    #[pyo3(signature = (plane="M"))]
+    fn mirror(&self, plane: &str) -> PyResult<Algorithm> {
+        let moves = self
+            .notation()
+            .mirrored(plane)
+            .map_err(|e| PyValueError::new_err(e))?;
+        Ok(Algorithm::from_notation(moves, None))
+    }
+
+    /// The moves on normal, with the inverse part dropped
+    fn normal_only(&self) -> Algorithm {
+        let moves = self.notation();
+        Algorithm::from_notation(
+            Notation {
+                normal: moves.normal,
+                inverse: vec![],
+            },
+            None,
+        )
+    }
+
+    /// The moves on inverse, with the normal part dropped
+    fn inverse_only(&self) -> Algorithm {
+        let moves = self.notation();
+        Algorithm::from_notation(
+            Notation {
+                normal: vec![],
+                inverse: moves.inverse,
+            },
+            None,
+        )
+    }
+
+    /// The smallest standard subgroup the moves belong to: "HTR" if they are all half
+    /// turns, "2-gen" for two adjacent faces, otherwise the layers used as written,
+    /// e.g. "<U,D>", "<R,L,U,D>" or "<U,M>". Rotations are left out.
+    fn group(&self) -> String {
+        let notation = self.notation();
+        let moves: Vec<Move> = notation
+            .normal
+            .into_iter()
+            .chain(notation.inverse)
+            .filter(|m| !m.is_rotation())
+            .collect();
+        if !moves.is_empty() && moves.iter().all(|m| m.quarters == 2) {
+            return "HTR".to_string();
+        }
+        // "r" is another name for "Rw"
+        let layer = |m: &Move| match m.layer {
+            "u" | "d" | "f" | "b" | "r" | "l" => format!("{}w", m.layer.to_uppercase()),
+            layer => layer.to_string(),
+        };
+        let order = [
+            "R", "L", "U", "D", "F", "B", "Rw", "Lw", "Uw", "Dw", "Fw", "Bw", "M", "E", "S",
+        ];
+        let used: Vec<usize> = (0..order.len())
+            .filter(|i| moves.iter().any(|m| layer(m) == order[*i]))
+            .collect();
+        match used[..] {
+            // Two face turns on different axes
+            [a, b] if b < 6 && a / 2 != b / 2 => "2-gen".to_string(),
+            _ => format!(
+                "<{}>",
+                used.iter()
+                    .map(|i| order[*i])
+                    .collect::<Vec<&str>>()
+                    .join(",")
+            ),
+        }
+    }
+
+    /// If the algorithm, applied to a solved cube, cycles exactly three edges or three
+    /// corners without changing any orientation, the piece type ("edge" or "corner")
+    /// and the positions in cycle order
+    fn is_three_cycle(&self) -> Option<(String, Vec<u8>)> {
+        let mut cube = Cube333::default();
+        cube.apply_alg(&self.0);
+        let edges = cube.edges.get_edges();
+        let corners = cube.corners.get_corners();
+        let oriented = edges
+            .iter()
+            .all(|e| e.oriented_ud && e.oriented_fb && e.oriented_rl)
+            && corners.iter().all(|c| c.orientation == 0);
+        if !oriented {
+            return None;
+        }
+        let edge_ids: Vec<u8> = edges.iter().map(|e| e.id).collect();
+        let corner_ids: Vec<u8> = corners.iter().map(|c| c.id).collect();
+        let (kind, cycle) = match (&cycles(&edge_ids)[..], &cycles(&corner_ids)[..]) {
+            ([cycle], []) => ("edge", cycle.clone()),
+            ([], [cycle]) => ("corner", cycle.clone()),
+            _ => return None,
+        };
+        if cycle.len() != 3 {
+            return None;
+        }
+        Some((
+            kind.to_string(),
+            cycle.iter().map(|pos| *pos as u8).collect(),
+        ))
+    }
+
+    /// Rough number of regrips needed to execute the algorithm, inverse moves included
+    /// after the normal ones. R, L and U turns are done from the home grip; F, D and B
+    /// each need their own grip, and every change of grip, including the return to
+    /// home, counts as one regrip. Moves count as written: wide moves are gripped like
+    /// their face, M from home, E like D and S like F, and a rotation is one regrip.
+    fn regrip_estimate(&self) -> usize {
+        let grip = |m: &Move| match m.layer.chars().next().map(|c| c.to_ascii_uppercase()) {
+            Some(f @ ('F' | 'D' | 'B')) => f,
+            Some('E') => 'D',
+            Some('S') => 'F',
+            _ => 'H',
+        };
+        let notation = self.notation();
+        // The inverse moves undone after the normal ones, as on the flattened solution
+        let undone = notation.inverted().inverse;
+        let moves = notation.normal.iter().chain(undone.iter());
+        let mut current = 'H';
+        let mut regrips = 0;
+        for m in moves {
+            if m.is_rotation() {
+                regrips += 1;
+            } else if grip(m) != current {
+                regrips += 1;
+                current = grip(m);
+            }
+        }
+        regrips
+    }
+
+    /// True if the algorithm has no moves on the inverse
+    fn is_linear(&self) -> bool {
+        self.0.inverse_moves.is_empty()
+    }
+
+    /// An annotation such as "EO", shown after the moves as "// EO". `append` and
+    /// `merge` keep the comment of the algorithm they are called on.
+    #[getter]
+    fn comment(&self) -> Option<String> {
+        self.1.clone()
+    }
+
+    #[setter]
+    fn set_comment(&mut self, comment: Option<String>) {
+        self.1 = comment;
     }
 
     fn __repr__(&self) -> String {
-        format!("{}", self.0)
+        match &self.1 {
+            Some(comment) => format!("{} // {}", self.notation(), comment),
+            None => format!("{}", self.notation()),
+        }
+    }
+
+    /// Pickle support: the normal moves, the inverse moves and the comment
+    fn __getstate__(&self) -> (Vec<String>, Vec<String>, Option<String>) {
+        (self.normal_moves(), self.inverse_moves(), self.1.clone())
+    }
+
+    fn __setstate__(&mut self, state: (Vec<String>, Vec<String>, Option<String>)) -> PyResult<()> {
+        let (normal, inverse, comment) = state;
+        let moves = |moves: Vec<String>| -> PyResult<Vec<Move>> {
+            moves
+                .iter()
+                .map(|m| {
+                    Move::from_str(m)
+                        .map_err(|_| PyValueError::new_err(format!("Invalid move: {}", m)))
+                })
+                .collect()
+        };
+        let moves = Notation {
+            normal: moves(normal)?,
+            inverse: moves(inverse)?,
+        };
+        *self = Algorithm::from_notation(moves, comment);
+        Ok(())
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn __reduce__(
+        &self,
+        py: Python<'_>,
+    ) -> (
+        PyObject,
+        (String,),
+        (Vec<String>, Vec<String>, Option<String>),
+    ) {
+        (
+            py.get_type::<Algorithm>().into_py(py),
+            ("".to_string(),),
+            self.__getstate__(),
+        )
+    }
+
+    /// `a + b` is `a.merge(b)`: `b`'s normal moves are appended to `a`'s normal moves
+    /// and its inverse moves to `a`'s inverse moves, cancelling at each seam
+    fn __add__(&self, other: &Algorithm) -> Algorithm {
+        self.merge(other)
+    }
+
+    /// `~a` is `a.inverted()`
+    fn __invert__(&self) -> Algorithm {
+        self.inverted()
+    }
+
+    /// `a * n` is `a` merged with itself `n` times, so "R U" * 2 is "R U R U" and
+    /// "R" * 4 is empty
+    fn __mul__(&self, n: usize) -> Algorithm {
+        (0..n).fold(
+            Algorithm(LibAlgorithm::from_str("").unwrap(), self.1.clone(), None),
+            |acc, _| acc.merge(self),
+        )
+    }
+
+    /// Number of moves as written, matching indexing and iteration. See `len` for the
+    /// face turn count.
+    fn __len__(&self) -> usize {
+        self.all_moves().len()
+    }
+
+    /// The move at `index` in the normal moves followed by the inverse moves.
+    /// Negative indices count from the end.
+    fn __getitem__(&self, index: isize) -> PyResult<String> {
+        let moves = self.all_moves();
+        let i = if index < 0 {
+            index + moves.len() as isize
+        } else {
+            index
+        };
+        usize::try_from(i)
+            .ok()
+            .and_then(|i| moves.get(i).cloned())
+            .ok_or_else(|| PyIndexError::new_err("Algorithm index out of range"))
+    }
+
+    fn __iter__(&self) -> MoveIter {
+        MoveIter(self.all_moves().into_iter())
+    }
+
+    /// Structural equality: the same moves on each side, not the same cube state
+    fn __richcmp__(&self, other: &Algorithm, op: CompareOp, py: Python<'_>) -> PyObject {
+        let same = self.normal_moves() == other.normal_moves()
+            && self.inverse_moves() == other.inverse_moves();
+        match op {
+            CompareOp::Eq => same.into_py(py),
+            CompareOp::Ne => (!same).into_py(py),
+            _ => py.NotImplemented(),
+        }
+    }
+
+    fn __hash__(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.normal_moves().hash(&mut hasher);
+        self.inverse_moves().hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl Algorithm {
+    // Face turns are kept move for move; anything else is kept as written alongside its
+    // face turns relative to the centers
+    fn from_notation(moves: Notation, comment: Option<String>) -> Algorithm {
+        let alg = moves.expand();
+        if moves.is_face_turns() {
+            Algorithm(alg, comment, None)
+        } else {
+            Algorithm(alg, comment, Some(moves))
+        }
+    }
+
+    // The moves as written
+    fn notation(&self) -> Notation {
+        self.2
+            .clone()
+            .unwrap_or_else(|| Notation::from_alg(&self.0))
+    }
+
+    // Normal moves followed by inverse moves
+    fn all_moves(&self) -> Vec<String> {
+        let mut moves = self.normal_moves();
+        moves.extend(self.inverse_moves());
+        moves
+    }
+}
+
+/// The moves of an algorithm, normal side first
+#[pyclass]
+struct MoveIter(std::vec::IntoIter<String>);
+
+#[pymethods]
+impl MoveIter {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> Option<String> {
+        self.0.next()
+    }
+}
+
+// Axis and number of clockwise quarter turns of a rotation such as "x", "y2" or "z'"
+fn parse_rotation(rotation: &str) -> PyResult<(char, usize)> {
+    let invalid = || PyValueError::new_err(format!("Invalid rotation: {}", rotation));
+    let mut chars = rotation.trim().chars();
+    let axis = chars
+        .next()
+        .filter(|c| "xyz".contains(*c))
+        .ok_or_else(invalid)?;
+    let times = match chars.as_str() {
+        "" => 1,
+        "2" => 2,
+        "'" => 3,
+        _ => return Err(invalid()),
+    };
+    Ok((axis, times))
+}
+
+// The cube transformation for a rotation, with the number of times to apply it
+fn parse_transformation(rotation: &str) -> PyResult<(Transformation333, usize)> {
+    let (axis, times) = parse_rotation(rotation)?;
+    let t = match axis {
+        'x' => Transformation333::X,
+        'y' => Transformation333::Y,
+        _ => Transformation333::Z,
+    };
+    Ok((t, times))
+}
+
+// Parses face turns with optional NISS parentheses, falling back to a normalized
+// form for sequences with wide moves, slice moves or rotations. Those are folded into
+// face turns relative to the centers, so "Rw" reads as "L" and the notation is lost.
+// Normalized turns go through `append_move`, so repeated slices cancel: "M M" is
+// "L2 R2" and "M M'" is empty.
+pub(crate) fn parse_alg(s: &str) -> Option<LibAlgorithm> {
+    if let Ok(alg) = LibAlgorithm::from_str(s) {
+        return Some(alg);
+    }
+    normalize(s)
+        .ok()?
+        .split_whitespace()
+        .try_fold(LibAlgorithm::from_str("").ok()?, |alg, t| {
+            Some(append_move(&alg, Turn333::from_str(t).ok()?, false))
+        })
+}
+
+// Number of moves removed by cancellation when appending `b` to `a`
+fn count_cancellations(a: &Algorithm, b: &Algorithm) -> usize {
+    a.len() + b.len() - a.merge(b).len()
+}
+
+// Pairs each solution with its length after merging onto `prefix`, shortest first
+fn rank_by_net_length(prefix: &Algorithm, solutions: Vec<Algorithm>) -> Vec<(Algorithm, usize)> {
+    let mut ranked: Vec<(Algorithm, usize)> = solutions
+        .into_iter()
+        .map(|alg| {
+            let net = prefix.len() + alg.len() - count_cancellations(prefix, &alg);
+            (alg, net)
+        })
+        .collect();
+    ranked.sort_by_key(|(_, net)| *net);
+    ranked
+}
+
+// Length of the shortest solution to the default next step, or 0 if there is none
+fn next_step_difficulty(kind: &str, variant: &str, cube: &Cube333) -> PyResult<usize> {
+    let (next_kind, next_variant) = match next_steps(kind, variant).first() {
+        Some(next) => *next,
+        None => return Ok(0),
+    };
+    let next = StepBuilder::from_kind(next_kind, next_variant)
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    shortest_solution_length(next.as_ref(), cube, 1)
+}
+
+// Length of the shortest of the first `count` solutions, 0 if the step is already solved
+// and usize::MAX if none are found
+fn shortest_solution_length(step: &dyn Solvable, cube: &Cube333, count: usize) -> PyResult<usize> {
+    if step.is_solved(cube) {
+        return Ok(0);
+    }
+    Ok(step
+        .solve(cube, count)?
+        .iter()
+        .map(|alg| alg.len())
+        .min()
+        .unwrap_or(usize::MAX))
+}
+
+/// Drops every solution that is dominated by another one. A solution is dominated if
+/// some other solution is strictly shorter and also leaves a strictly shorter solution
+/// to the next step. Order is preserved.
+#[pyfunction]
+fn prune_dominated(
+    kind: &str,
+    variant: &str,
+    cube: &Cube,
+    algs: Vec<Algorithm>,
+) -> PyResult<Vec<Algorithm>> {
+    let scored = algs
+        .into_iter()
+        .map(|alg| {
+            let mut c = cube.0;
+            c.apply_alg(&alg.0);
+            let difficulty = next_step_difficulty(kind, variant, &c)?;
+            Ok((alg.len(), difficulty, alg))
+        })
+        .collect::<PyResult<Vec<(usize, usize, Algorithm)>>>()?;
+    Ok(scored
+        .iter()
+        .filter(|(len, difficulty, _)| !scored.iter().any(|(l, d, _)| l < len && d < difficulty))
+        .map(|(_, _, alg)| alg.clone())
+        .collect())
+}
+
+// Drops the trailing normal moves that turn one of the given faces
+fn without_auf(alg: &LibAlgorithm, faces: &str) -> LibAlgorithm {
+    let mut alg = alg.clone();
+    while let Some(t) = alg.normal_moves.last() {
+        if !faces.contains(&format!("{}", t)[..1]) {
+            break;
+        }
+        alg.normal_moves.pop();
+    }
+    alg
+}
+
+/// The most advanced step that is solved on the cube, as (kind, variant). When several
+/// axes are solved, the first of ud, fb, rl is reported. ("", "") if nothing is.
+#[pyfunction]
+fn furthest_step(cube: &Cube) -> PyResult<(String, String)> {
+    let stages = [
+        ("finish", vec![""]),
+        ("fr", vec!["ud", "fb", "rl"]),
+        ("htr", vec!["ud", "fb", "rl"]),
+        ("dr", vec!["ud", "fb", "rl"]),
+        ("eo", vec!["ud", "fb", "rl"]),
+    ];
+    for (kind, variants) in stages {
+        for variant in variants {
+            let step = StepBuilder::from_kind(kind, variant)
+                .map_err(|e| PyValueError::new_err(e.to_string()))?;
+            if step.is_solved(&cube.0) {
+                return Ok((kind.to_string(), variant.to_string()));
+            }
+        }
+    }
+    Ok(("".to_string(), "".to_string()))
+}
+
+/// Solves the next step. Without a plan, that is the default step after
+/// `furthest_step`; with a plan such as "EO > DR[ud]", it is the first step of the plan
+/// not yet solved on the cube. Returns no solutions if there is nothing left to solve.
+#[pyfunction]
+#[pyo3(signature = (cube, count, plan=None))]
+fn smart_solve(cube: &Cube, count: usize, plan: Option<&str>) -> PyResult<Vec<Algorithm>> {
+    let plan = match plan {
+        None => {
+            let (kind, variant) = furthest_step(cube)?;
+            return match next_steps(&kind, &variant).first() {
+                Some((next_kind, next_variant)) if kind != "finish" => {
+                    StepBuilder::from_kind(next_kind, next_variant)
+                        .map_err(|e| PyValueError::new_err(e))?
+                        .solve(&cube.0, count)
+                }
+                _ => Ok(vec![]),
+            };
+        }
+        Some(plan) => parse_steps(plan).map_err(|e| PyValueError::new_err(e))?,
+    };
+    for cfg in plan {
+        if !plan_step_solved(&cfg, &cube.0)? {
+            return solve_step(&cube.0, cfg, count, false, StepFilters::default());
+        }
+    }
+    Ok(vec![])
+}
+
+// True if any of the variants a plan step allows is solved on the cube
+fn plan_step_solved(cfg: &StepConfig, cube: &Cube333) -> PyResult<bool> {
+    let kind = match cfg.kind {
+        StepKind::EO => "eo",
+        StepKind::DR => "dr",
+        StepKind::HTR => "htr",
+        StepKind::FR | StepKind::FRLS => "fr",
+        _ => "finish",
+    };
+    let variants: Vec<String> = match (kind, &cfg.substeps) {
+        ("finish", _) => vec!["".to_string()],
+        (_, Some(substeps)) => substeps
+            .iter()
+            .map(|v| match &v[0..2.min(v.len())] {
+                "lr" => "rl".to_string(),
+                axis => axis.to_string(),
+            })
+            .collect(),
+        (_, None) => vec!["ud".to_string(), "fb".to_string(), "rl".to_string()],
+    };
+    for variant in variants {
+        let step = StepBuilder::from_kind(kind, &variant).map_err(|e| PyValueError::new_err(e))?;
+        if step.is_solved(cube) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Decodes the output of `StepInfo.solve_packed`
+#[pyfunction]
+fn unpack_solutions(bytes: Vec<u8>) -> PyResult<Vec<Algorithm>> {
+    let truncated = || PyValueError::new_err("Truncated solution data");
+    let mut algs = vec![];
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let counts = bytes.get(pos..pos + 2).ok_or_else(truncated)?;
+        let n_normal = counts[0] as usize;
+        let end = pos + 2 + n_normal + counts[1] as usize;
+        let moves = bytes.get(pos + 2..end).ok_or_else(truncated)?;
+        let mut alg = LibAlgorithm::from_str("").unwrap();
+        for (i, b) in moves.iter().enumerate() {
+            let turn = FACE_TURNS
+                .get(*b as usize)
+                .ok_or_else(|| PyValueError::new_err(format!("Invalid move byte: {}", b)))?;
+            let turn = Turn333::from_str(turn).unwrap();
+            if i < n_normal {
+                alg.normal_moves.push(turn);
+            } else {
+                alg.inverse_moves.push(turn);
+            }
+        }
+        algs.push(Algorithm::from(alg));
+        pos = end;
+    }
+    Ok(algs)
+}
+
+/// The face turns that can undo the step, found by applying each one to a solved cube
+/// and checking whether the step is still solved. For DR-UD these are the R, L, F
+/// and B quarter turns.
+#[pyfunction]
+fn influencing_moves(kind: &str, variant: &str) -> PyResult<Vec<String>> {
+    let step =
+        StepBuilder::from_kind(kind, variant).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Ok(FACE_TURNS
+        .iter()
+        .filter(|t| {
+            let mut cube = Cube333::default();
+            cube.apply_alg(&LibAlgorithm::from_str(t).unwrap());
+            !step.is_solved(&cube)
+        })
+        .map(|t| t.to_string())
+        .collect())
+}
+
+/// Applies `iterations` random algorithms and checks that the cube stays valid, that
+/// each alg followed by its inverse restores the solved cube, that inverting the cube
+/// matches applying the inverted alg, and that cube deltas and facelets round-trip.
+/// Raises on the first inconsistency.
+#[pyfunction]
+fn self_check(iterations: usize, seed: u64) -> PyResult<()> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let fail = |check: &str, alg: &Algorithm| {
+        Err(PyValueError::new_err(format!(
+            "{} failed for {}",
+            check,
+            alg.__repr__()
+        )))
+    };
+    for _ in 0..iterations {
+        let len = rng.random_range(1..=30);
+        let moves: Vec<&str> = (0..len)
+            .map(|_| FACE_TURNS[rng.random_range(0..FACE_TURNS.len())])
+            .collect();
+        let alg = Algorithm::new(&moves.join(" "))?;
+
+        let mut cube = Cube::new(alg.__repr__())?;
+        if let Some(e) = cube.validity_error() {
+            return fail(&format!("Validity ({})", e), &alg);
+        }
+        let scrambled = cube.clone();
+
+        if Cube::parse_facelets(&cube.facelets()?)?.coord_key() != cube.coord_key() {
+            return fail("Facelets round-trip", &alg);
+        }
+
+        let mut inverted = cube.clone();
+        inverted.invert();
+        let expected = Cube::new(alg.inverted().__repr__())?;
+        if inverted.edges()? != expected.edges()? || inverted.corners()? != expected.corners()? {
+            return fail("Invert", &alg);
+        }
+
+        cube.apply(&alg.inverted());
+        if !is_fully_solved(&cube.0) {
+            return fail("Inverse", &alg);
+        }
+
+        cube.apply_delta(cube.delta_to(&scrambled))?;
+        if cube.edges()? != scrambled.edges()? || cube.corners()? != scrambled.corners()? {
+            return fail("Delta", &alg);
+        }
+    }
+    Ok(())
+}
+
+const LOWER_BOUND_DEPTH: usize = 4;
+
+// Largest threshold `is_trivial_scramble` accepts. The search below it already takes
+// seconds on scrambles whose lower bound is far from their distance.
+const MAX_TRIVIAL_THRESHOLD: u8 = 10;
+
+/// True if the scramble can be solved in fewer than `threshold` moves, which may be at
+/// most 10. Scrambles whose pruning table lower bound reaches the threshold are
+/// rejected at once; the others are decided by an exact search.
+#[pyfunction]
+fn is_trivial_scramble(scramble: &str, threshold: u8) -> PyResult<bool> {
+    if threshold > MAX_TRIVIAL_THRESHOLD {
+        return Err(PyValueError::new_err(format!(
+            "threshold must be at most {}",
+            MAX_TRIVIAL_THRESHOLD
+        )));
+    }
+    let cube = Cube::new(scramble.to_string())?;
+    let threshold = threshold as usize;
+    if threshold == 0 || lower_bound(&cube.0) >= threshold {
+        return Ok(false);
     }
+    Ok(distance_within(&cube.0, threshold - 1).is_some())
+}
+
+/// `case_name` of the step for each cube, computed across all cores
+#[pyfunction]
+fn recognize_batch(
+    py: Python<'_>,
+    kind: &str,
+    variant: &str,
+    cubes: Vec<Cube>,
+) -> PyResult<Vec<String>> {
+    StepBuilder::from_kind(kind, variant).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = ((cubes.len() + threads - 1) / threads).max(1);
+    py.allow_threads(|| {
+        std::thread::scope(|s| {
+            let handles: Vec<_> = cubes
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    s.spawn(move || {
+                        let step = StepBuilder::from_kind(kind, variant).unwrap();
+                        chunk
+                            .iter()
+                            .map(|c| step.case_name(&c.0))
+                            .collect::<Vec<String>>()
+                    })
+                })
+                .collect();
+            let mut names = Vec::with_capacity(cubes.len());
+            for h in handles {
+                let chunk = h
+                    .join()
+                    .map_err(|_| PyRuntimeError::new_err("Recognition thread panicked"))?;
+                names.extend(chunk);
+            }
+            Ok(names)
+        })
+    })
+}
+
+/// Splices `insert` into the solution before move `at`, cancelling moves on both sides,
+/// and returns the new solution with the change in move count. NISS solutions are
+/// flattened onto normal first.
+#[pyfunction]
+fn apply_insertion(
+    solution: &Algorithm,
+    insert: &Algorithm,
+    at: usize,
+) -> PyResult<(Algorithm, i32)> {
+    let moves = solution.0.to_uninverted().normal_moves;
+    if at > moves.len() {
+        return Err(PyValueError::new_err(format!(
+            "Insertion point {} is past the end of the solution",
+            at
+        )));
+    }
+    let part = |moves: &[Turn333]| {
+        Algorithm::from(LibAlgorithm {
+            normal_moves: moves.to_vec(),
+            inverse_moves: vec![],
+        })
+    };
+    let result = part(&moves[..at])
+        .merge(&insert.all_on_normal())
+        .merge(&part(&moves[at..]));
+    let change = result.len() as i32 - moves.len() as i32;
+    Ok((result, change))
+}
+
+// Default execution cost of turning each face: R and U are easiest, B hardest
+const DEFAULT_FACE_COSTS: [(char, f64); 6] = [
+    ('R', 1.0),
+    ('U', 1.0),
+    ('F', 1.5),
+    ('L', 1.5),
+    ('D', 2.0),
+    ('B', 2.5),
+];
+
+// Sorts by length, then by the summed cost of each move's face. The sort is stable, so
+// solutions of equal length and cost keep their order.
+fn rank_by_face_cost(mut algs: Vec<Algorithm>, costs: &HashMap<char, f64>) -> Vec<Algorithm> {
+    let cost = |alg: &Algorithm| -> f64 {
+        alg.0
+            .normal_moves
+            .iter()
+            .chain(alg.0.inverse_moves.iter())
+            .map(|t| costs[&format!("{}", t).chars().next().unwrap()])
+            .sum()
+    };
+    algs.sort_by(|a, b| a.len().cmp(&b.len()).then(cost(a).total_cmp(&cost(b))));
+    algs
 }
 
 fn append_move(alg: &LibAlgorithm, turn: Turn333, inverse: bool) -> LibAlgorithm {
@@ -178,8 +1181,7 @@ struct Cube(Cube333);
 impl Cube {
     #[new]
     fn new(scramble: String) -> PyResult<Self> {
-        let alg = LibAlgorithm::from_str(&scramble)
-            .map_err(|_| PyValueError::new_err("Invalid scramble"))?;
+        let alg = parse_alg(&scramble).ok_or_else(|| PyValueError::new_err("Invalid scramble"))?;
         let mut cube = Cube333::default();
         cube.apply_alg(&alg);
         Ok(Cube(cube))
@@ -219,21 +1221,442 @@ impl Cube {
     fn invert(&mut self) {
         self.0.invert()
     }
-}
 
-// The Python module definition
-#[pymodule]
-fn vfmc_core(_py: Python, m: &PyModule) -> PyResult<()> {
-    // Register the classes
-    m.add_class::<Cube>()?;
-    m.add_class::<Algorithm>()?;
-    m.add_class::<StepInfo>()?;
-    m.add_class::<Solution>()?;
+    /// A lower bound on the length of an optimal solution. It is exact up to
+    /// `LOWER_BOUND_DEPTH` moves; beyond that it comes from pruning tables of corner
+    /// twist, edge flip, slice edge and corner permutation coordinates.
+    fn solution_lower_bound(&self) -> u8 {
+        distance_within(&self.0, LOWER_BOUND_DEPTH)
+            .unwrap_or_else(|| lower_bound(&self.0).max(LOWER_BOUND_DEPTH + 1)) as u8
+    }
 
-    m.add_function(wrap_pyfunction!(debug, m)?)?;
-    m.add_function(wrap_pyfunction!(scramble, m)?)?;
-    Ok(())
-}
+    /// Applies the moves to the inverse of the cube, as if they were in parentheses
+    fn apply_alg_on_inverse_string(&mut self, s: &str) -> PyResult<()> {
+        let alg = parse_alg(s)
+            .ok_or_else(|| PyValueError::new_err(format!("Invalid algorithm: {}", s)))?;
+        self.0.invert();
+        self.0.apply_alg(&alg);
+        self.0.invert();
+        Ok(())
+    }
+
+    /// Color index (as in FaceletColors) of the sticker at `row`, `col` of `face`
+    fn color_at(&self, face: &str, row: usize, col: usize) -> PyResult<u8> {
+        color_at(self, face, row, col)
+    }
+
+    fn is_valid(&self) -> bool {
+        self.validity_error().is_none()
+    }
+
+    /// Describes why this state can't be reached from a solved cube, if it can't.
+    fn validity_error(&self) -> Option<String> {
+        let edges = self.0.edges.get_edges();
+        let corners = self.0.corners.get_corners();
+        let edge_ids: Vec<u8> = edges.iter().map(|e| e.id).collect();
+        let corner_ids: Vec<u8> = corners.iter().map(|c| c.id).collect();
+        if (0..12).any(|id| !edge_ids.contains(&id)) {
+            return Some("Edges are not a permutation".to_string());
+        }
+        if (0..8).any(|id| !corner_ids.contains(&id)) {
+            return Some("Corners are not a permutation".to_string());
+        }
+        if edges.iter().filter(|e| !e.oriented_fb).count() % 2 != 0 {
+            return Some("Flipped edge".to_string());
+        }
+        if corners
+            .iter()
+            .map(|c| c.orientation as usize)
+            .sum::<usize>()
+            % 3
+            != 0
+        {
+            return Some("Twisted corner".to_string());
+        }
+        if permutation_parity(&edge_ids) != permutation_parity(&corner_ids) {
+            return Some("Swapped pieces".to_string());
+        }
+        None
+    }
+
+    /// A move sequence that produces this state from a solved cube. Not optimal.
+    fn scramble_string(&self) -> PyResult<String> {
+        let mut alg = solve_pipeline(self.0).map_err(|e| PyValueError::new_err(e))?;
+        alg.invert();
+        Ok(format!("{}", alg))
+    }
+
+    /// The pieces that differ in `other`, as (position, id, orientation) byte triples.
+    /// Edge positions are 0-11, with orientation bits ud, fb, rl; corner positions are
+    /// 12-19.
+    fn delta_to(&self, other: &Cube) -> Vec<u8> {
+        let bits = |e: &Edge| {
+            e.oriented_ud as u8 | (e.oriented_fb as u8) << 1 | (e.oriented_rl as u8) << 2
+        };
+        let mut delta = vec![];
+        let (edges, other_edges) = (self.0.edges.get_edges(), other.0.edges.get_edges());
+        for pos in 0..12 {
+            let (e, o) = (edges[pos], other_edges[pos]);
+            if e.id != o.id || bits(&e) != bits(&o) {
+                delta.extend([pos as u8, o.id, bits(&o)]);
+            }
+        }
+        let (corners, other_corners) =
+            (self.0.corners.get_corners(), other.0.corners.get_corners());
+        for pos in 0..8 {
+            let (c, o) = (corners[pos], other_corners[pos]);
+            if c.id != o.id || c.orientation != o.orientation {
+                delta.extend([12 + pos as u8, o.id, o.orientation]);
+            }
+        }
+        delta
+    }
+
+    /// Applies a delta produced by `delta_to`
+    fn apply_delta(&mut self, delta: Vec<u8>) -> PyResult<()> {
+        if delta.len() % 3 != 0 {
+            return Err(PyValueError::new_err("Invalid delta length"));
+        }
+        let mut edges = self.0.edges.get_edges();
+        let mut corners = self.0.corners.get_corners();
+        for change in delta.chunks(3) {
+            match (change[0] as usize, change[1], change[2]) {
+                (pos @ 0..=11, id @ 0..=11, bits @ 0..=7) => {
+                    edges[pos].id = id;
+                    edges[pos].oriented_ud = bits & 1 != 0;
+                    edges[pos].oriented_fb = bits & 2 != 0;
+                    edges[pos].oriented_rl = bits & 4 != 0;
+                }
+                (pos @ 12..=19, id @ 0..=7, orientation @ 0..=2) => {
+                    corners[pos - 12].id = id;
+                    corners[pos - 12].orientation = orientation;
+                }
+                _ => {
+                    return Err(PyValueError::new_err(format!(
+                        "Invalid delta entry: {:?}",
+                        change
+                    )))
+                }
+            }
+        }
+        self.0 = Cube333::new(EdgeCube333::new(edges), CornerCube333::new(corners));
+        Ok(())
+    }
+
+    /// The raw edge and corner state, equal for equal cubes
+    fn coord_key(&self) -> (u64, u64, u64) {
+        let edges = self.0.edges.get_edges_raw();
+        (edges[0], edges[1], self.0.corners.get_corners_raw())
+    }
+
+    /// The name of a famous position, if this is one
+    fn well_known_name(&self) -> Option<String> {
+        WELL_KNOWN_POSITIONS
+            .iter()
+            .find(|(_, alg)| Cube::new(alg.to_string()).unwrap().coord_key() == self.coord_key())
+            .map(|(name, _)| name.to_string())
+    }
+
+    /// A uniformly random state. The same `seed` gives the same cube with a given
+    /// build, but not necessarily across releases.
+    #[staticmethod]
+    #[pyo3(signature = (seed=None))]
+    fn random(seed: Option<u64>) -> Cube {
+        match seed {
+            Some(seed) => Cube(Cube333::random(&mut StdRng::seed_from_u64(seed))),
+            None => Cube(Cube333::random(&mut rand::rng())),
+        }
+    }
+
+    /// Reorients the cube by a rotation such as "x", "y2" or "z'"
+    fn transform(&mut self, rotation: &str) -> PyResult<()> {
+        let (t, times) = parse_transformation(rotation)?;
+        for _ in 0..times {
+            self.0.transform(t);
+        }
+        Ok(())
+    }
+
+    /// Rewrites `alg`, a solution found on a cube after `transform(rotation)`, so that
+    /// it applies to the cube before the rotation
+    #[staticmethod]
+    fn transform_solution_frame(rotation: &str, alg: &Algorithm) -> PyResult<Algorithm> {
+        let (t, times) = parse_transformation(rotation)?;
+        let alg = (0..times).fold(alg.0.clone(), |alg, _| transform_solution_frame(&alg, t));
+        Ok(Algorithm::from(alg))
+    }
+
+    /// Pickle support: the state is every piece that differs from a solved cube, in the
+    /// format of `delta_to`
+    fn __getstate__(&self) -> Vec<u8> {
+        Cube(Cube333::default()).delta_to(self)
+    }
+
+    fn __setstate__(&mut self, state: Vec<u8>) -> PyResult<()> {
+        let mut cube = Cube(Cube333::default());
+        cube.apply_delta(state)?;
+        if let Some(error) = cube.validity_error() {
+            return Err(PyValueError::new_err(format!(
+                "Invalid cube state: {}",
+                error
+            )));
+        }
+        self.0 = cube.0;
+        Ok(())
+    }
+
+    fn __reduce__(&self, py: Python<'_>) -> (PyObject, (String,), Vec<u8>) {
+        (
+            py.get_type::<Cube>().into_py(py),
+            ("".to_string(),),
+            self.__getstate__(),
+        )
+    }
+
+    /// An independent copy; applying moves to it leaves this cube unchanged
+    fn copy(&self) -> Cube {
+        self.clone()
+    }
+
+    fn __copy__(&self) -> Cube {
+        self.clone()
+    }
+
+    fn __deepcopy__(&self, _memo: &PyAny) -> Cube {
+        self.clone()
+    }
+
+    /// Equal if the pieces are in the same places, however the states were reached
+    fn __richcmp__(&self, other: &Cube, op: CompareOp, py: Python<'_>) -> PyObject {
+        let same = self.coord_key() == other.coord_key();
+        match op {
+            CompareOp::Eq => same.into_py(py),
+            CompareOp::Ne => (!same).into_py(py),
+            _ => py.NotImplemented(),
+        }
+    }
+
+    fn __hash__(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.coord_key().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Facelet string for Kociemba's two-phase solver
+    fn to_kociemba(&self) -> PyResult<String> {
+        kociemba_string(self)
+    }
+
+    /// Reads a string in the format of `facelets`. Raises if it has the wrong length,
+    /// unknown letters, or describes a state no sequence of moves can reach.
+    #[staticmethod]
+    #[pyo3(name = "from_facelets")]
+    fn parse_facelets(facelets: &str) -> PyResult<Cube> {
+        from_facelets(facelets)
+    }
+
+    /// The 54 facelets in U R F D L B order, each named by the face of its color.
+    /// Same as `to_kociemba`; `from_facelets` reads it back.
+    fn facelets(&self) -> PyResult<String> {
+        kociemba_string(self)
+    }
+
+    /// Quick subgroup checks. Each mirrors `is_solved` of the step of the same name.
+    fn is_eo(&self, axis: &str) -> PyResult<bool> {
+        match axis {
+            "ud" => Ok(self.0.count_bad_edges_ud() == 0),
+            "fb" => Ok(self.0.count_bad_edges_fb() == 0),
+            "rl" => Ok(self.0.count_bad_edges_lr() == 0),
+            _ => Err(unknown_axis(axis)),
+        }
+    }
+
+    fn is_dr(&self, axis: &str) -> PyResult<bool> {
+        match axis {
+            "ud" => Ok(DRUD.is_solved(&self.0)),
+            "fb" => Ok(DRFB.is_solved(&self.0)),
+            "rl" => Ok(DRRL.is_solved(&self.0)),
+            _ => Err(unknown_axis(axis)),
+        }
+    }
+
+    fn is_htr(&self) -> bool {
+        HTRUD.is_solved(&self.0)
+    }
+
+    fn is_fr(&self, axis: &str) -> PyResult<bool> {
+        match axis {
+            "ud" => Ok(FRUD.is_solved(&self.0)),
+            "fb" => Ok(FRFB.is_solved(&self.0)),
+            "rl" => Ok(FRRL.is_solved(&self.0)),
+            _ => Err(unknown_axis(axis)),
+        }
+    }
+
+    /// For each edge position, whether the edge there is oriented relative to the
+    /// "ud", "fb" or "rl" axis
+    fn edge_orientation_vector(&self, reference: &str) -> PyResult<Vec<bool>> {
+        let edges = self.0.edges.get_edges();
+        match reference {
+            "ud" => Ok(edges.iter().map(|e| e.oriented_ud).collect()),
+            "fb" => Ok(edges.iter().map(|e| e.oriented_fb).collect()),
+            "rl" => Ok(edges.iter().map(|e| e.oriented_rl).collect()),
+            _ => Err(unknown_axis(reference)),
+        }
+    }
+
+    /// For each corner position, whether the corner there shows its reference-axis
+    /// color on the "ud", "fb" or "rl" faces
+    fn corner_orientation_vector(&self, reference: &str) -> PyResult<Vec<bool>> {
+        let corners = self.0.corners.get_corners();
+        let oriented = |pos: usize| match reference {
+            "ud" => Ok(corners[pos].oriented_ud(pos as u8)),
+            "fb" => Ok(corners[pos].oriented_fb(pos as u8)),
+            "rl" => Ok(corners[pos].oriented_rl(pos as u8)),
+            _ => Err(unknown_axis(reference)),
+        };
+        (0..8).map(oriented).collect()
+    }
+
+    /// The single U or D turn that solves the cube, or None if it needs anything else
+    /// (including nothing)
+    fn auf_to_solve(&self) -> Option<String> {
+        ["U", "U2", "U'", "D", "D2", "D'"]
+            .iter()
+            .find(|t| {
+                let mut c = self.0;
+                c.apply_alg(&LibAlgorithm::from_str(t).unwrap());
+                is_fully_solved(&c)
+            })
+            .map(|t| t.to_string())
+    }
+
+    /// The state reflected through the M, S or E slice
+    fn mirror(&self, plane: &str) -> PyResult<Cube> {
+        let (edge_map, corner_map) = match plane.to_uppercase().as_str() {
+            "M" => (MIRROR_M_EDGES, MIRROR_M_CORNERS),
+            "S" => (MIRROR_S_EDGES, MIRROR_S_CORNERS),
+            "E" => (MIRROR_E_EDGES, MIRROR_E_CORNERS),
+            _ => return Err(PyValueError::new_err(format!("Invalid plane: {}", plane))),
+        };
+        // Each axis maps to itself, so edge orientation is kept, while the reflection
+        // reverses the direction of every corner twist
+        let old_edges = self.0.edges.get_edges();
+        let mut edges = self.0.edges.get_edges();
+        for (pos, e) in old_edges.iter().enumerate() {
+            let new = &mut edges[edge_map[pos]];
+            new.id = edge_map[e.id as usize] as u8;
+            new.oriented_ud = e.oriented_ud;
+            new.oriented_fb = e.oriented_fb;
+            new.oriented_rl = e.oriented_rl;
+        }
+        let old_corners = self.0.corners.get_corners();
+        let mut corners = self.0.corners.get_corners();
+        for (pos, c) in old_corners.iter().enumerate() {
+            let new = &mut corners[corner_map[pos]];
+            new.id = corner_map[c.id as usize] as u8;
+            new.orientation = (3 - c.orientation) % 3;
+        }
+        Ok(Cube(Cube333::new(
+            EdgeCube333::new(edges),
+            CornerCube333::new(corners),
+        )))
+    }
+}
+
+// Where each edge and corner position goes when the cube is reflected through a slice
+const MIRROR_M_EDGES: [usize; 12] = [0, 3, 2, 1, 5, 4, 7, 6, 8, 11, 10, 9];
+const MIRROR_M_CORNERS: [usize; 8] = [1, 0, 3, 2, 5, 4, 7, 6];
+const MIRROR_S_EDGES: [usize; 12] = [2, 1, 0, 3, 6, 7, 4, 5, 10, 9, 8, 11];
+const MIRROR_S_CORNERS: [usize; 8] = [3, 2, 1, 0, 7, 6, 5, 4];
+const MIRROR_E_EDGES: [usize; 12] = [10, 9, 8, 11, 4, 5, 6, 7, 2, 1, 0, 3];
+const MIRROR_E_CORNERS: [usize; 8] = [7, 6, 5, 4, 3, 2, 1, 0];
+
+// Famous positions and an algorithm that reaches each one
+const WELL_KNOWN_POSITIONS: [(&str, &str); 5] = [
+    ("solved", ""),
+    (
+        "superflip",
+        "U R2 F B R B2 R U2 L B2 R U' D' R2 F R' L B2 U2 F2",
+    ),
+    ("checkerboard", "U2 D2 F2 B2 L2 R2"),
+    ("cube in cube", "F L F U' R U F2 L2 U' L' B D' B' L2 U"),
+    ("6 spot", "U D' R L' F B' U D'"),
+];
+
+fn unknown_axis(axis: &str) -> PyErr {
+    PyValueError::new_err(format!("Unknown axis: {}", axis))
+}
+
+// True if each of the given edges and corners is in place and oriented
+fn pieces_solved(cube: &Cube333, edges: &[usize], corners: &[usize]) -> bool {
+    let all_edges = cube.edges.get_edges();
+    let all_corners = cube.corners.get_corners();
+    edges.iter().all(|pos| {
+        let e = all_edges[*pos];
+        e.id as usize == *pos && e.oriented_ud && e.oriented_fb
+    }) && corners.iter().all(|pos| {
+        let c = all_corners[*pos];
+        c.id as usize == *pos && c.orientation == 0
+    })
+}
+
+// True if the permutation is odd
+fn permutation_parity(ids: &[u8]) -> bool {
+    let mut inversions = 0;
+    for i in 0..ids.len() {
+        for j in i + 1..ids.len() {
+            if ids[i] > ids[j] {
+                inversions += 1;
+            }
+        }
+    }
+    inversions % 2 == 1
+}
+
+// The Python module definition
+#[pymodule]
+fn vfmc_core(_py: Python, m: &PyModule) -> PyResult<()> {
+    // Register the classes
+    m.add_class::<Cube>()?;
+    m.add_class::<Algorithm>()?;
+    m.add_class::<StepInfo>()?;
+    m.add_class::<Solution>()?;
+    m.add_class::<SolutionIter>()?;
+    m.add_class::<MoveIter>()?;
+    m.add_class::<Session>()?;
+    m.add_class::<Sticker>()?;
+
+    m.add_function(wrap_pyfunction!(debug, m)?)?;
+    m.add_function(wrap_pyfunction!(solve_plan_iter, m)?)?;
+    m.add_function(wrap_pyfunction!(prune_dominated, m)?)?;
+    m.add_function(wrap_pyfunction!(furthest_step, m)?)?;
+    m.add_function(wrap_pyfunction!(recognize_batch, m)?)?;
+    m.add_function(wrap_pyfunction!(is_trivial_scramble, m)?)?;
+    m.add_function(wrap_pyfunction!(influencing_moves, m)?)?;
+    m.add_function(wrap_pyfunction!(self_check, m)?)?;
+    m.add_function(wrap_pyfunction!(solve_stats, m)?)?;
+    m.add_function(wrap_pyfunction!(apply_insertion, m)?)?;
+    m.add_function(wrap_pyfunction!(best_insertion, m)?)?;
+    m.add_function(wrap_pyfunction!(scramble, m)?)?;
+    m.add_function(wrap_pyfunction!(scramble_cube, m)?)?;
+    m.add_function(wrap_pyfunction!(scramble_seeded_in_range, m)?)?;
+    m.add_function(wrap_pyfunction!(invert_scramble, m)?)?;
+    m.add_function(wrap_pyfunction!(canonical_scramble, m)?)?;
+    m.add_function(wrap_pyfunction!(normalize_scramble, m)?)?;
+    m.add_function(wrap_pyfunction!(to_cubedb, m)?)?;
+    m.add_function(wrap_pyfunction!(from_cubedb, m)?)?;
+    m.add_function(wrap_pyfunction!(from_facelets, m)?)?;
+    m.add_function(wrap_pyfunction!(from_scan, m)?)?;
+    m.add_function(wrap_pyfunction!(eo_both_axes, m)?)?;
+    m.add_function(wrap_pyfunction!(min_eo, m)?)?;
+    m.add_function(wrap_pyfunction!(available_dr_axes, m)?)?;
+    m.add_function(wrap_pyfunction!(eodr_overlay, m)?)?;
+    m.add_function(wrap_pyfunction!(smart_solve, m)?)?;
+    m.add_function(wrap_pyfunction!(unpack_solutions, m)?)?;
+    m.add_function(wrap_pyfunction!(finish_length, m)?)?;
+    Ok(())
+}
 
 // trait DrawableEdge {
 //     fn facelet_showing_ud(&self) -> Option<u8>;
@@ -308,6 +1731,28 @@ impl DrawableCorner for Corner {
     }
 }
 
+/// Filters for `StepInfo.solve`, all off by default
+#[derive(Clone, Default)]
+pub struct SolveOptions {
+    /// DR only: the bad corner count of the case the final trigger is applied to
+    pub target_corners: Option<u8>,
+    /// HTR only: solve for the domino on this axis
+    pub axis: Option<String>,
+    /// Reject solutions with inverse moves
+    pub require_linear: bool,
+    /// HTR only: the last move must be a half turn on this axis
+    pub last_half_turn: Option<String>,
+    /// Reject solutions ending with a prime turn, and solutions starting with one when
+    /// the unprimed start reaches the same case
+    pub canonical_both_ends: bool,
+    /// If the step is already solved, return the empty alg first
+    pub include_trivial: bool,
+    /// DR only: at most this many solutions per resulting HTR subset
+    pub per_subset: Option<usize>,
+    /// Keep one solution of each set equal under cube symmetry
+    pub dedup_symmetry: bool,
+}
+
 #[derive(Clone)]
 #[pyclass]
 pub struct StepInfo {
@@ -321,6 +1766,124 @@ impl StepInfo {
     fn step(&self) -> Result<Box<dyn Solvable>, String> {
         StepBuilder::from_kind(&self.kind, &self.variant)
     }
+
+    // The step's name in reconstructions: "eofb", "drud", or just the kind for steps
+    // that take their axis from an earlier one
+    fn label(&self) -> String {
+        match self.kind.as_str() {
+            "htr" | "finish" | "insertions" => self.kind.clone(),
+            kind => format!("{}{}", kind, self.variant),
+        }
+    }
+
+    // Up to `count` solutions, restricted by the filters in `options`
+    fn solve_with(
+        &self,
+        cube: &Cube,
+        count: usize,
+        options: &SolveOptions,
+    ) -> PyResult<Vec<Algorithm>> {
+        // An already-solved step gets the empty alg first, then the usual search
+        let trivial = options.include_trivial && count > 0 && self.is_solved(cube)?;
+        let count = if trivial { count - 1 } else { count };
+        let mut filters = vec![];
+        let mut caps = vec![];
+        // A step solved by a prime last turn is also solved by the clockwise one, which
+        // leaves the same case up to that turn. Prime starts are only dropped when the
+        // clockwise start reaches the same case id. That never happens on steps told
+        // apart by exact state, only on grouped ones like HTR.
+        if options.canonical_both_ends {
+            filters.push(FilterLastMoveNotPrime::new());
+        }
+        if options.require_linear {
+            filters.push(FilterLinear::new());
+        }
+        if options.dedup_symmetry {
+            caps.push(symmetry_dedup_cap());
+        }
+        if let Some(last_axis) = &options.last_half_turn {
+            if self.kind != "htr" {
+                return Err(PyValueError::new_err(format!(
+                    "last_half_turn is not supported for {}",
+                    self.kind
+                )));
+            }
+            filters.push(last_half_turn_filter(last_axis)?);
+        }
+        if let Some(n) = options.target_corners {
+            if self.kind != "dr" {
+                return Err(PyValueError::new_err(format!(
+                    "target_corners is not supported for {}",
+                    self.kind
+                )));
+            }
+            filters.push(corner_count_filter(&cube.0, &self.variant, n as usize)?);
+        }
+        if let Some(n) = options.per_subset {
+            if self.kind != "dr" {
+                return Err(PyValueError::new_err(format!(
+                    "per_subset is not supported for {}",
+                    self.kind
+                )));
+            }
+            caps.push(subset_cap(&self.variant, n)?);
+        }
+        let filters = StepFilters {
+            predicates: filters,
+            caps,
+            canonical_start: options.canonical_both_ends,
+        };
+        let mut solutions = match (self.kind.as_str(), options.axis.as_deref()) {
+            (_, None) => self
+                .step()
+                .map_err(|e| PyValueError::new_err(e.to_string()))?
+                .solve_filtered(&cube.0, count, filters)?,
+            ("htr", Some(axis)) => solve_htr(&cube.0, axis, count, filters)?,
+            (kind, Some(_)) => {
+                return Err(PyValueError::new_err(format!(
+                    "axis is not supported for {}",
+                    kind
+                )))
+            }
+        };
+        if trivial {
+            solutions.retain(|alg| !alg.is_empty());
+            solutions.insert(0, Algorithm::new("")?);
+        }
+        Ok(solutions)
+    }
+
+    // Up to `count` solutions accepted by `predicate`. The solver runs on worker
+    // threads that cannot take the GIL while the caller holds it, so the predicate is
+    // applied afterwards, asking for more solutions until enough pass.
+    fn solve_matching<F>(
+        &self,
+        cube: &Cube,
+        count: usize,
+        mut predicate: F,
+    ) -> PyResult<Vec<Algorithm>>
+    where
+        F: FnMut(&Algorithm) -> PyResult<bool>,
+    {
+        let mut requested = count;
+        loop {
+            let solutions = self.solve_with(cube, requested, &SolveOptions::default())?;
+            let exhausted = solutions.len() < requested || requested >= 10000;
+            let mut accepted = vec![];
+            for alg in solutions {
+                if predicate(&alg)? {
+                    accepted.push(alg);
+                }
+                if accepted.len() == count {
+                    return Ok(accepted);
+                }
+            }
+            if exhausted {
+                return Ok(accepted);
+            }
+            requested = (requested * 2).min(10000);
+        }
+    }
 }
 
 #[pymethods]
@@ -356,39 +1919,410 @@ impl StepInfo {
         }
     }
 
+    fn corner_pattern(&self, cube: &Cube) -> PyResult<String> {
+        let mut c = cube.0.clone();
+        match (self.kind.as_str(), self.variant.as_str()) {
+            ("htr", "ud") => Ok(HTRUD.corner_pattern(&c)),
+            ("htr", "fb") => {
+                c.transform(Transformation333::X);
+                Ok(HTRUD.corner_pattern(&c))
+            }
+            ("htr", "rl") => {
+                c.transform(Transformation333::Z);
+                Ok(HTRUD.corner_pattern(&c))
+            }
+            (kind, _) => Err(PyValueError::new_err(format!(
+                "corner_pattern is not supported for {}",
+                kind
+            ))),
+        }
+    }
+
+    /// For EO, the name of a quick case such as "4-flip layer" or "2-flip adjacent",
+    /// with the layers taken around the EO axis
+    fn fast_case(&self, cube: &Cube) -> PyResult<Option<String>> {
+        let mut c = cube.0.clone();
+        match (self.kind.as_str(), self.variant.as_str()) {
+            ("eo", "ud") => Ok(EOUD.fast_case(&c)),
+            ("eo", "fb") => {
+                c.transform(Transformation333::X);
+                Ok(EOUD.fast_case(&c))
+            }
+            ("eo", "rl") => {
+                c.transform(Transformation333::Z);
+                Ok(EOUD.fast_case(&c))
+            }
+            (kind, _) => Err(PyValueError::new_err(format!(
+                "fast_case is not supported for {}",
+                kind
+            ))),
+        }
+    }
+
+    /// Other ways to finish the same case: `alg` with its final turns of the step's
+    /// axis replaced, and its mirror. Only variations that still solve the step are
+    /// returned.
+    fn variations(&self, cube: &Cube, alg: &Algorithm) -> PyResult<Vec<Algorithm>> {
+        let faces = match self.variant.as_str() {
+            "fb" => "FB",
+            "rl" => "RL",
+            _ => "UD",
+        };
+        let mut candidates = vec![alg.clone(), alg.mirror("M")?];
+        for base in candidates.clone() {
+            let base = Algorithm::from(without_auf(&base.0, faces));
+            candidates.push(base.clone());
+            for face in faces.chars() {
+                for dir in ["", "2", "'"] {
+                    candidates.push(base.append(&format!("{}{}", face, dir), false)?);
+                }
+            }
+        }
+        let mut seen = vec![alg.__repr__()];
+        let mut variations = vec![];
+        for candidate in candidates {
+            let mut c = cube.clone();
+            c.apply(&candidate);
+            if !seen.contains(&candidate.__repr__()) && self.is_solved(&c)? {
+                seen.push(candidate.__repr__());
+                variations.push(candidate);
+            }
+        }
+        Ok(variations)
+    }
+
+    /// The edge and corner slots whose piece or orientation `alg` would change
+    fn solution_affects(&self, cube: &Cube, alg: &Algorithm) -> (Vec<usize>, Vec<usize>) {
+        let mut after = cube.clone();
+        after.apply(alg);
+        let (mut edges, mut corners) = (vec![], vec![]);
+        for change in cube.delta_to(&after).chunks(3) {
+            match change[0] as usize {
+                pos @ 0..=11 => edges.push(pos),
+                pos => corners.push(pos - 12),
+            }
+        }
+        (edges, corners)
+    }
+
+    /// True if this is a DR that is already solved where it could be started
+    fn is_free(&self, cube: &Cube) -> PyResult<bool> {
+        match (self.kind.as_str(), self.variant.as_str()) {
+            ("dr", "ud") => Ok(DRUD.is_free(&cube.0)),
+            ("dr", "fb") => Ok(DRFB.is_free(&cube.0)),
+            ("dr", "rl") => Ok(DRRL.is_free(&cube.0)),
+            (kind, _) => Err(PyValueError::new_err(format!(
+                "is_free is not supported for {}",
+                kind
+            ))),
+        }
+    }
+
+    /// For finish, up to `count` solutions, each with the cycles it resolves, e.g.
+    /// "solves UBL-UFR-UBR corner cycle"
+    fn solve_cycle_info(&self, cube: &Cube, count: usize) -> PyResult<Vec<(Algorithm, String)>> {
+        match self.kind.as_str() {
+            "finish" => Finish.solve_cycle_info(&cube.0, count),
+            kind => Err(PyValueError::new_err(format!(
+                "solve_cycle_info is not supported for {}",
+                kind
+            ))),
+        }
+    }
+
+    /// For finish and insertions, the cycle each misplaced edge and corner belongs to
+    fn cycle_visibility(&self, cube: &Cube) -> PyResult<(Vec<Option<usize>>, Vec<Option<usize>>)> {
+        match self.kind.as_str() {
+            "finish" | "insertions" => Ok(cycle_groups(&cube.0)),
+            kind => Err(PyValueError::new_err(format!(
+                "cycle_visibility is not supported for {}",
+                kind
+            ))),
+        }
+    }
+
     fn edge_visibility(&self, cube: &Cube) -> PyResult<Vec<(u8, u8)>> {
         let step = self
             .step()
             .map_err(|e| PyValueError::new_err(e.to_string()))?;
-        let mut vec = vec![];
-        for i in 0..12 {
-            vec.push((
-                step.edge_visibility(&cube.0, i, 0) as u8,
-                step.edge_visibility(&cube.0, i, 1) as u8,
-            ));
-        }
-        Ok(vec)
+        Ok(edge_visibilities(step.as_ref(), &CubeView::new(&cube.0)))
     }
 
     fn corner_visibility(&self, cube: &Cube) -> PyResult<Vec<(u8, u8, u8)>> {
         let step = self
             .step()
             .map_err(|e| PyValueError::new_err(e.to_string()))?;
-        let mut vec = vec![];
-        for i in 0..8 {
-            vec.push((
-                step.corner_visibility(&cube.0, i, 0) as u8,
-                step.corner_visibility(&cube.0, i, 1) as u8,
-                step.corner_visibility(&cube.0, i, 2) as u8,
-            ));
+        Ok(corner_visibilities(step.as_ref(), &CubeView::new(&cube.0)))
+    }
+
+    /// Edge and corner visibility together, decoding the cube only once per frame
+    fn describe(&self, cube: &Cube) -> PyResult<(Vec<(u8, u8)>, Vec<(u8, u8, u8)>)> {
+        let step = self
+            .step()
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let view = CubeView::new(&cube.0);
+        Ok((
+            edge_visibilities(step.as_ref(), &view),
+            corner_visibilities(step.as_ref(), &view),
+        ))
+    }
+
+    /// Sticker colors and visibility for drawing the cube as a net
+    fn render_data(&self, cube: &Cube) -> PyResult<Vec<Sticker>> {
+        let step = self
+            .step()
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        render_data(cube, step.as_ref())
+    }
+
+    /// Up to `count` solutions. The filters are keyword-only; see `SolveOptions`.
+    #[pyo3(signature = (
+        cube,
+        count,
+        *,
+        target_corners=None,
+        axis=None,
+        require_linear=false,
+        last_half_turn=None,
+        canonical_both_ends=false,
+        include_trivial=false,
+        per_subset=None,
+        dedup_symmetry=false
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn solve(
+        &self,
+        cube: &Cube,
+        count: usize,
+        target_corners: Option<u8>,
+        axis: Option<String>,
+        require_linear: bool,
+        last_half_turn: Option<String>,
+        canonical_both_ends: bool,
+        include_trivial: bool,
+        per_subset: Option<usize>,
+        dedup_symmetry: bool,
+    ) -> PyResult<Vec<Algorithm>> {
+        let options = SolveOptions {
+            target_corners,
+            axis,
+            require_linear,
+            last_half_turn,
+            canonical_both_ends,
+            include_trivial,
+            per_subset,
+            dedup_symmetry,
+        };
+        self.solve_with(cube, count, &options)
+    }
+
+    /// Solutions paired with the remaining step distance before each move and after
+    /// the last one. The distance is the length of the shortest solution the solver
+    /// finds from that point, so it falls by one per move along an optimal solution.
+    /// Inverse moves are counted after the normal ones.
+    fn solve_with_distances(
+        &self,
+        cube: &Cube,
+        count: usize,
+    ) -> PyResult<Vec<(Algorithm, Vec<u8>)>> {
+        let step = self
+            .step()
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let distance = |c: &Cube333| -> PyResult<u8> {
+            if step.is_solved(c) {
+                return Ok(0);
+            }
+            step.solve(c, 1)?
+                .first()
+                .map(|alg| alg.len() as u8)
+                .ok_or_else(|| PyValueError::new_err("No solution found"))
+        };
+        let mut result = vec![];
+        for alg in step.solve(&cube.0, count)? {
+            let distances = states_along(&cube.0, &alg.0)
+                .iter()
+                .map(|c| distance(c))
+                .collect::<PyResult<Vec<u8>>>()?;
+            result.push((alg, distances));
         }
-        Ok(vec)
+        Ok(result)
     }
 
-    fn solve(&self, cube: &Cube, count: usize) -> PyResult<Vec<Algorithm>> {
+    /// Solutions that never disturb the given pieces, which must be solved to begin
+    /// with. Every intermediate state is checked, not just the result.
+    fn solve_preserving(
+        &self,
+        cube: &Cube,
+        edges: Vec<usize>,
+        corners: Vec<usize>,
+        count: usize,
+    ) -> PyResult<Vec<Algorithm>> {
+        if edges.iter().any(|e| *e > 11) || corners.iter().any(|c| *c > 7) {
+            return Err(PyValueError::new_err("Invalid piece position"));
+        }
+        if !pieces_solved(&cube.0, &edges, &corners) {
+            return Err(PyValueError::new_err("Pieces to preserve are not solved"));
+        }
+        let filter = FilterEveryState::new(cube.0, move |c: &Cube333| {
+            pieces_solved(c, &edges, &corners)
+        });
         self.step()
             .map_err(|e| PyValueError::new_err(e.to_string()))?
-            .solve(&cube.0, count)
+            .solve_filtered(&cube.0, count, vec![filter].into())
+    }
+
+    /// Solutions ordered by length, and among equal lengths by execution cost. `costs`
+    /// maps faces ("R", "U", ...) to the cost of turning them and overrides the
+    /// defaults: R and U 1.0, F and L 1.5, D 2.0, B 2.5.
+    #[pyo3(signature = (cube, count, costs=None))]
+    fn solve_ergonomic(
+        &self,
+        cube: &Cube,
+        count: usize,
+        costs: Option<HashMap<String, f64>>,
+    ) -> PyResult<Vec<Algorithm>> {
+        let mut face_costs: HashMap<char, f64> = DEFAULT_FACE_COSTS.iter().cloned().collect();
+        for (face, cost) in costs.unwrap_or_default() {
+            match face.to_uppercase().chars().collect::<Vec<char>>()[..] {
+                [f] if face_costs.contains_key(&f) => face_costs.insert(f, cost),
+                _ => return Err(PyValueError::new_err(format!("Invalid face: {}", face))),
+            };
+        }
+        let solutions = self.solve_with(cube, count, &SolveOptions::default())?;
+        Ok(rank_by_face_cost(solutions, &face_costs))
+    }
+
+    /// Solutions paired with the length of the best `next` step that follows them,
+    /// looking at `next_count` solutions of `next`. Sorted by that length.
+    fn solve_chaining(
+        &self,
+        cube: &Cube,
+        next: &StepInfo,
+        next_count: usize,
+        count: usize,
+    ) -> PyResult<Vec<(Algorithm, usize)>> {
+        let next_step = next
+            .step()
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let mut ranked = vec![];
+        for alg in self.solve_with(cube, count, &SolveOptions::default())? {
+            let mut c = cube.clone();
+            c.apply(&alg);
+            let next_len = shortest_solution_length(next_step.as_ref(), &c.0, next_count)?;
+            ranked.push((alg, next_len));
+        }
+        ranked.sort_by_key(|(_, next_len)| *next_len);
+        Ok(ranked)
+    }
+
+    /// Solutions with at most one per key: "case" (the step's case name after the
+    /// solution), "subset" (the DR subset after the solution), "first3" (the first
+    /// three moves) or "none". Solutions reaching the same state are always merged.
+    fn solve_deduped_by(&self, cube: &Cube, count: usize, key: &str) -> PyResult<Vec<Algorithm>> {
+        let step = self
+            .step()
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let (kind, variant) = (self.kind.clone(), self.variant.clone());
+        let caps = match key {
+            "none" => vec![],
+            "case" => vec![CaseCap::new(1, move |c: &Cube333, _alg: &LibAlgorithm| {
+                StepBuilder::from_kind(&kind, &variant)
+                    .unwrap()
+                    .case_name(c)
+            })],
+            "subset" => vec![CaseCap::new(1, |c: &Cube333, _alg: &LibAlgorithm| {
+                c.get_dr_subset().map(|s| s.to_string()).unwrap_or_default()
+            })],
+            "first3" => vec![CaseCap::new(1, |_c: &Cube333, alg: &LibAlgorithm| {
+                Algorithm::from(alg.clone())
+                    .normal_moves()
+                    .into_iter()
+                    .take(3)
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            })],
+            _ => return Err(PyValueError::new_err(format!("Unknown dedup key: {}", key))),
+        };
+        let filters = StepFilters {
+            caps,
+            ..Default::default()
+        };
+        step.solve_filtered(&cube.0, count, filters)
+    }
+
+    /// Solutions as reconstruction lines: "<alg> // <case> (<length>)", where the case
+    /// is the step's case name for the state the solution reaches
+    fn solve_annotated(&self, cube: &Cube, count: usize) -> PyResult<Vec<String>> {
+        let step = self
+            .step()
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let mut lines = vec![];
+        for alg in self.solve_with(cube, count, &SolveOptions::default())? {
+            let mut c = cube.clone();
+            c.apply(&alg);
+            lines.push(format!(
+                "{} // {} ({})",
+                alg.__repr__(),
+                step.case_name(&c.0),
+                alg.len()
+            ));
+        }
+        Ok(lines)
+    }
+
+    /// Solutions for which `predicate(alg)` is true. An exception raised by the
+    /// predicate is raised from here.
+    fn solve_filtered(
+        &self,
+        py: Python<'_>,
+        cube: &Cube,
+        count: usize,
+        predicate: PyObject,
+    ) -> PyResult<Vec<Algorithm>> {
+        self.solve_matching(cube, count, |alg| {
+            predicate.call1(py, (alg.clone(),))?.extract::<bool>(py)
+        })
+    }
+
+    /// All solutions in one byte string, decoded by `unpack_solutions`. Each solution is
+    /// the number of normal moves, the number of inverse moves, then one byte per move:
+    /// its index in U, U2, U', D, D2, D', F, F2, F', B, B2, B', R, R2, R', L, L2, L'.
+    fn solve_packed(&self, cube: &Cube, count: usize) -> PyResult<Vec<u8>> {
+        let mut bytes = vec![];
+        for alg in self.solve_with(cube, count, &SolveOptions::default())? {
+            let (normal, inverse) = (alg.normal_moves(), alg.inverse_moves());
+            bytes.push(normal.len() as u8);
+            bytes.push(inverse.len() as u8);
+            for m in normal.iter().chain(inverse.iter()) {
+                bytes.push(FACE_TURNS.iter().position(|t| t == m).unwrap() as u8);
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Number of distinct solutions at each length, shortest first, among the first
+    /// `max_examined` solutions found
+    fn solution_spectrum(&self, cube: &Cube, max_examined: usize) -> PyResult<Vec<(u8, usize)>> {
+        let mut seen = HashSet::new();
+        let mut counts: BTreeMap<u8, usize> = BTreeMap::new();
+        for alg in self.solve_with(cube, max_examined, &SolveOptions::default())? {
+            if seen.insert(alg.__repr__()) {
+                *counts.entry(alg.len() as u8).or_default() += 1;
+            }
+        }
+        Ok(counts.into_iter().collect())
+    }
+
+    /// Solutions for the cube reached after `prefix`, with the length of `prefix` plus
+    /// the solution after cancellation. Sorted by that length.
+    fn solve_ranked_for_prefix(
+        &self,
+        cube: &Cube,
+        prefix: &Algorithm,
+        count: usize,
+    ) -> PyResult<Vec<(Algorithm, usize)>> {
+        let solutions = self.solve_with(cube, count, &SolveOptions::default())?;
+        Ok(rank_by_net_length(prefix, solutions))
     }
 
     fn solve_steps(&self, cube: &Cube, count: usize, steps_str: &str) -> PyResult<Vec<Solution>> {
@@ -401,36 +2335,14 @@ impl StepInfo {
 
         steps.apply_step_limit(100);
         let solutions = steps.into_worker(cube).take(count);
-        let mut py_solutions = vec![];
-        for sol in solutions {
-            let mut py_steps = vec![];
-            let mut py_algs = vec![];
-            for step in sol.get_steps() {
-                let variant = match step.kind {
-                    StepKind::EO | StepKind::HTR | StepKind::FR => &step.variant,
-                    StepKind::DR => &step.variant[0..2],
-                    _ => "",
-                };
-                let variant = match variant {
-                    "lr" => "rl".to_string(),
-                    other => other.to_string(),
-                };
-                py_steps.push(StepInfo {
-                    kind: step.kind.to_string(),
-                    variant: variant,
-                });
-                py_algs.push(Algorithm(step.alg.clone()));
-            }
-            py_solutions.push(Solution {
-                steps: py_steps,
-                algs: py_algs,
-            });
-        }
-        Ok(py_solutions)
+        Ok(solutions.map(|sol| Solution::from_lib(&sol)).collect())
     }
 
+    /// `StepInfo("", "")` is the scrambled step: it is always solved and shows every
+    /// sticker as `Any`. Raises for an unknown step or variant.
     #[new]
     fn new(kind: &str, variant: &str) -> PyResult<Self> {
+        StepBuilder::from_kind(kind, variant).map_err(|e| PyValueError::new_err(e))?;
         Ok(StepInfo {
             kind: kind.to_string(),
             variant: variant.to_string(),
@@ -438,21 +2350,69 @@ impl StepInfo {
     }
 }
 
+fn edge_visibilities(step: &dyn Solvable, view: &CubeView) -> Vec<(u8, u8)> {
+    (0..12)
+        .map(|i| {
+            (
+                step.edge_visibility(view, i, 0),
+                step.edge_visibility(view, i, 1),
+            )
+        })
+        .collect()
+}
+
+fn corner_visibilities(step: &dyn Solvable, view: &CubeView) -> Vec<(u8, u8, u8)> {
+    (0..8)
+        .map(|i| {
+            (
+                step.corner_visibility(view, i, 0),
+                step.corner_visibility(view, i, 1),
+                step.corner_visibility(view, i, 2),
+            )
+        })
+        .collect()
+}
+
+// The pieces of a cube, decoded once and shared by all the visibility lookups for
+// one frame
+pub struct CubeView {
+    pub edges: [Edge; 12],
+    pub corners: [Corner; 8],
+}
+
+impl CubeView {
+    pub fn new(cube: &Cube333) -> Self {
+        CubeView {
+            edges: cube.edges.get_edges(),
+            corners: cube.corners.get_corners(),
+        }
+    }
+}
+
 pub enum Visibility {
     Any = 1,
     BadFace = 2,
     BadPiece = 4,
     HtrD = 8,
     TopColor = 16,
+    All = 255,
 }
 
 trait Solvable {
     fn is_solved(&self, cube: &Cube333) -> bool;
     fn is_eligible(&self, cube: &Cube333) -> bool;
     fn case_name(&self, cube: &Cube333) -> String;
-    fn edge_visibility(&self, cube: &Cube333, pos: usize, facelet: u8) -> u8;
-    fn corner_visibility(&self, cube: &Cube333, pos: usize, facelet: u8) -> u8;
-    fn solve(&self, cube: &Cube333, count: usize) -> PyResult<Vec<Algorithm>>;
+    fn edge_visibility(&self, cube: &CubeView, pos: usize, facelet: u8) -> u8;
+    fn corner_visibility(&self, cube: &CubeView, pos: usize, facelet: u8) -> u8;
+    fn solve(&self, cube: &Cube333, count: usize) -> PyResult<Vec<Algorithm>> {
+        self.solve_filtered(cube, count, StepFilters::default())
+    }
+    fn solve_filtered(
+        &self,
+        cube: &Cube333,
+        count: usize,
+        filters: StepFilters,
+    ) -> PyResult<Vec<Algorithm>>;
 }
 struct StepBuilder;
 impl StepBuilder {
@@ -496,6 +2456,7 @@ impl StepBuilder {
     }
 }
 
+// No step selected yet. Nothing is highlighted.
 pub struct SCRAMBLED;
 impl Solvable for SCRAMBLED {
     fn is_solved(&self, _cube: &Cube333) -> bool {
@@ -507,13 +2468,18 @@ impl Solvable for SCRAMBLED {
     fn case_name(&self, _cube: &Cube333) -> String {
         "".to_string()
     }
-    fn edge_visibility(&self, _cube: &Cube333, _pos: usize, _facelet: u8) -> u8 {
+    fn edge_visibility(&self, _cube: &CubeView, _pos: usize, _facelet: u8) -> u8 {
         Any as u8
     }
-    fn corner_visibility(&self, _cube: &Cube333, _pos: usize, _facelet: u8) -> u8 {
+    fn corner_visibility(&self, _cube: &CubeView, _pos: usize, _facelet: u8) -> u8 {
         Any as u8
     }
-    fn solve(&self, _cube: &Cube333, _count: usize) -> PyResult<Vec<Algorithm>> {
+    fn solve_filtered(
+        &self,
+        _cube: &Cube333,
+        _count: usize,
+        _filters: StepFilters,
+    ) -> PyResult<Vec<Algorithm>> {
         Err(PyValueError::new_err("Direct solver is not implemented"))
     }
 }
@@ -575,9 +2541,229 @@ const CORNER_OPPOSITE_M_SLICE: [u8; 8] = [1, 0, 3, 2, 5, 4, 7, 6];
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::orientation::scramble_symmetries;
     use crate::Solvable;
     use crate::Visibility::BadFace;
 
+    #[test]
+    fn wide_moves() {
+        let mut wide = Cube::new("Rw U".to_string()).unwrap();
+        let mut slice = Cube::new("R M' U".to_string()).unwrap();
+        assert_eq!(wide.coord_key(), slice.coord_key());
+        assert_eq!(Algorithm::new("r U R'").unwrap().__repr__(), "r U R'");
+        assert_eq!(format!("{}", Algorithm::new("r U R'").unwrap().0), "L F R'");
+
+        let rw = Algorithm::new("Rw").unwrap();
+        wide = Cube::new("".to_string()).unwrap();
+        wide.apply(&rw);
+        slice = Cube::new("".to_string()).unwrap();
+        slice.apply(&Algorithm::new("R M'").unwrap());
+        assert_eq!(wide.coord_key(), slice.coord_key());
+        assert!(Algorithm::new("").unwrap().append("Q", false).is_err());
+
+        // Appended wide moves are kept as written and turn the same layers as a face
+        // turn with the slice next to it
+        let rw = Algorithm::new("").unwrap().append("Rw", false).unwrap();
+        assert_eq!(rw.__repr__(), "Rw");
+        assert_eq!(Algorithm::new(&rw.__repr__()).unwrap().__repr__(), "Rw");
+        let r_m = Algorithm::new("R").unwrap().append("M'", false).unwrap();
+        assert_eq!(r_m.__repr__(), "R M'");
+        wide = Cube::new("".to_string()).unwrap();
+        wide.apply(&rw);
+        slice = Cube::new("".to_string()).unwrap();
+        slice.apply(&r_m);
+        assert_eq!(wide.coord_key(), slice.coord_key());
+
+        let rw_u = rw.append("U", false).unwrap();
+        assert_eq!(rw_u.__repr__(), "Rw U");
+        wide = Cube::new("".to_string()).unwrap();
+        wide.apply(&rw_u);
+        slice = Cube::new("".to_string()).unwrap();
+        slice.apply(&r_m.append("U", false).unwrap());
+        assert_eq!(wide.coord_key(), slice.coord_key());
+
+        let alg = Algorithm::new("Rw").unwrap().append("r", false).unwrap();
+        assert_eq!(alg.__repr__(), "Rw2");
+        assert!(alg.append("Rw2", false).unwrap().is_empty());
+        assert_eq!(Algorithm::new("r U r'").unwrap().__repr__(), "r U r'");
+        assert_eq!(Algorithm::new("(Fw)").unwrap().__repr__(), "(Fw)");
+    }
+
+    #[test]
+    fn slice_moves() {
+        let repr = |s: &str| Algorithm::new(s).unwrap().__repr__();
+        assert_eq!(repr("M"), "M");
+        assert_eq!(repr("M M"), "M2");
+        assert_eq!(repr("M M'"), "");
+        assert_eq!(repr("M2 M2"), "");
+        assert_eq!(repr("E E"), "E2");
+        assert_eq!(repr("E E'"), "");
+        assert_eq!(repr("S S"), "S2");
+        assert_eq!(repr("S' S"), "");
+        assert_eq!(repr("M R M'"), "R");
+        assert_eq!(repr("M U M'"), "M U M'");
+        assert_eq!(repr("R (M' M')"), "R (M2)");
+        assert_eq!(format!("{}", Algorithm::new("M M").unwrap().0), "L2 R2");
+        assert_eq!(
+            Algorithm::new("M U").unwrap().inverted().__repr__(),
+            "U' M'"
+        );
+        assert!(Algorithm::new("M (U").is_err());
+
+        // The face turns follow the centers, so they match the cube the moves leave
+        let alg = Algorithm::new("M U M' U2").unwrap();
+        let mut cube = Cube::new("".to_string()).unwrap();
+        cube.apply(&alg);
+        let expected = Cube::new("M U M' U2".to_string()).unwrap();
+        assert_eq!(cube.coord_key(), expected.coord_key());
+    }
+
+    #[test]
+    fn algorithm_append_slices() {
+        for (slice, face, other) in [("M", "R", "U"), ("E", "D", "F"), ("S", "B", "R")] {
+            let prime = format!("{}'", slice);
+            let alg = Algorithm::new("").unwrap();
+            let alg = alg.append(slice, false).unwrap();
+            assert_eq!(alg.__repr__(), slice);
+            let alg = alg.append(slice, false).unwrap();
+            assert_eq!(alg.__repr__(), format!("{}2", slice));
+            let alg = alg.append(slice, false).unwrap();
+            assert_eq!(alg.__repr__(), prime);
+            let alg = alg.append(slice, false).unwrap();
+            assert!(alg.is_empty());
+
+            // A face turn on the same axis commutes with the slice, so the slice
+            // cancels past it
+            let alg = alg.append(slice, false).unwrap();
+            let alg = alg.append(face, false).unwrap();
+            let alg = alg.append(&prime, false).unwrap();
+            assert_eq!(alg.__repr__(), face);
+
+            // A turn on another axis does not
+            let alg = Algorithm::new(slice).unwrap();
+            let alg = alg.append(other, false).unwrap();
+            let alg = alg.append(&prime, false).unwrap();
+            assert_eq!(alg.__repr__(), format!("{} {} {}", slice, other, prime));
+
+            let alg = Algorithm::new("").unwrap();
+            let alg = alg.append(slice, true).unwrap();
+            assert_eq!(alg.__repr__(), format!("({})", slice));
+            let alg = alg.append(&prime, true).unwrap();
+            assert!(alg.is_empty());
+
+            let slice_then_face = Algorithm::new(slice)
+                .unwrap()
+                .merge(&Algorithm::new(face).unwrap());
+            assert_eq!(slice_then_face.__repr__(), format!("{} {}", slice, face));
+            assert_eq!(
+                slice_then_face
+                    .merge(&Algorithm::new(&prime).unwrap())
+                    .__repr__(),
+                face
+            );
+        }
+    }
+
+    #[test]
+    fn algorithm_from_moves() {
+        let moves = |s: &str| s.split_whitespace().map(|m| m.to_string()).collect();
+        let alg = Algorithm::new("R U2 F' (B D)").unwrap();
+        let normal = Algorithm::from_moves(alg.normal_moves(), false).unwrap();
+        assert_eq!(normal.__repr__(), "R U2 F'");
+        let inverse = Algorithm::from_moves(alg.inverse_moves(), true).unwrap();
+        assert_eq!(inverse.__repr__(), "(B D)");
+        assert_eq!(
+            Algorithm::from_moves(moves("R U U' R2"), false)
+                .unwrap()
+                .__repr__(),
+            "R'"
+        );
+        let err = Algorithm::from_moves(moves("R X U"), false).unwrap_err();
+        assert!(err.to_string().contains("Invalid move: X"));
+    }
+
+    #[test]
+    fn algorithm_cancellation_count() {
+        let count = |a: &str, b: &str| {
+            Algorithm::new(a)
+                .unwrap()
+                .cancellation_count(&Algorithm::new(b).unwrap())
+        };
+        assert_eq!(count("R U2", "U2 R'"), 4);
+        assert_eq!(count("R L", "L' R'"), 4);
+        assert_eq!(count("R2", "R2"), 2);
+        assert_eq!(count("R", "R"), 1);
+        assert_eq!(count("R U", "R"), 0);
+        assert_eq!(count("(F)", "(F')"), 2);
+    }
+
+    #[test]
+    fn append_across_opposite_face() {
+        let append_all = |s: &str| {
+            s.split_whitespace()
+                .fold(Algorithm::new("").unwrap(), |alg, m| {
+                    alg.append(m, false).unwrap()
+                })
+                .__repr__()
+        };
+        assert_eq!(append_all("R L R"), "R2 L");
+        assert_eq!(append_all("R L R'"), "L");
+        assert_eq!(append_all("F B F2"), "F' B");
+        assert_eq!(append_all("R L U R"), "R L U R");
+    }
+
+    #[test]
+    fn algorithm_indexing() {
+        let alg = Algorithm::new("R U2 (F' D)").unwrap();
+        assert_eq!(alg.__len__(), 4);
+        assert_eq!(alg.__getitem__(0).unwrap(), "R");
+        assert_eq!(alg.__getitem__(2).unwrap(), "F'");
+        assert_eq!(alg.__getitem__(-1).unwrap(), "D");
+        assert_eq!(alg.__getitem__(-4).unwrap(), "R");
+        assert!(alg.__getitem__(4).is_err());
+        assert!(alg.__getitem__(-5).is_err());
+        let moves: Vec<String> = alg.__iter__().0.collect();
+        assert_eq!(moves, vec!["R", "U2", "F'", "D"]);
+        let alg = Algorithm::new("M Rw (x)").unwrap();
+        assert_eq!(alg.__len__(), 3);
+        assert_eq!(alg.__getitem__(1).unwrap(), "Rw");
+        assert_eq!(alg.__getitem__(-1).unwrap(), "x");
+        assert!(alg.__getitem__(3).is_err());
+        let moves: Vec<String> = alg.__iter__().0.collect();
+        assert_eq!(moves, vec!["M", "Rw", "x"]);
+    }
+
+    #[test]
+    fn algorithm_comment() {
+        let mut alg = Algorithm::new("R U").unwrap();
+        assert_eq!(alg.comment(), None);
+        alg.set_comment(Some("EO".to_string()));
+        assert_eq!(alg.__repr__(), "R U // EO");
+        let appended = alg.append("F", false).unwrap();
+        assert_eq!(appended.__repr__(), "R U F // EO");
+        let mut other = Algorithm::new("D").unwrap();
+        other.set_comment(Some("DR".to_string()));
+        assert_eq!(alg.merge(&other).__repr__(), "R U D // EO");
+        assert_eq!(other.merge(&alg).__repr__(), "D R U // DR");
+        alg.set_comment(None);
+        assert_eq!(alg.__repr__(), "R U");
+    }
+
+    #[test]
+    fn algorithm_operators() {
+        let a = Algorithm::new("R U (F)").unwrap();
+        let b = Algorithm::new("U' D (F)").unwrap();
+        assert_eq!(a.__add__(&b).__repr__(), "R D (F2)");
+        assert_eq!(a.__invert__().__repr__(), a.inverted().__repr__());
+        assert_eq!(
+            Algorithm::new("R U").unwrap().__mul__(2).__repr__(),
+            "R U R U"
+        );
+        assert_eq!(Algorithm::new("R").unwrap().__mul__(2).__repr__(), "R2");
+        assert!(Algorithm::new("R").unwrap().__mul__(4).is_empty());
+        assert!(a.__mul__(0).is_empty());
+    }
+
     #[test]
     fn algorithm_append() {
         let alg = Algorithm::new("").unwrap();
@@ -609,12 +2795,1123 @@ mod tests {
         assert_eq!(format!("{}", alg.0), "F (F' B2)");
     }
 
+    #[test]
+    fn algorithm_htm_with_niss() {
+        let alg = Algorithm::new("F R (U R)").unwrap();
+        assert_eq!(alg.len(), 4);
+        assert_eq!(alg.htm_with_niss(), 2);
+        let alg = Algorithm::new("F R").unwrap();
+        assert_eq!(alg.htm_with_niss(), alg.len());
+    }
+
+    #[test]
+    fn algorithm_rotate_to_frame() {
+        let alg = Algorithm::new("R U F (D')").unwrap();
+        let rotated = alg.rotate_solution_to_frame("uf", "ur").unwrap();
+        assert_eq!(format!("{}", rotated.0), "F U L (D')");
+        let back = rotated.rotate_solution_to_frame("ur", "uf").unwrap();
+        assert_eq!(format!("{}", back.0), "R U F (D')");
+
+        let scramble = Algorithm::new("R' U' F D2 L B' U R2 F'").unwrap();
+        let solution = scramble.inverted();
+        for to in ["fu", "rb", "dl"] {
+            let mut cube = Cube::new("".to_string()).unwrap();
+            cube.apply(&scramble.rotate_solution_to_frame("uf", to).unwrap());
+            cube.apply(&solution.rotate_solution_to_frame("uf", to).unwrap());
+            assert_eq!(
+                cube.corners().unwrap(),
+                Cube::new("".to_string()).unwrap().corners().unwrap()
+            );
+            assert_eq!(
+                cube.edges().unwrap(),
+                Cube::new("".to_string()).unwrap().edges().unwrap()
+            );
+        }
+        assert!(alg.rotate_solution_to_frame("uf", "uu").is_err());
+        let alg = Algorithm::new("Rw U M").unwrap();
+        let rotated = alg.rotate_solution_to_frame("uf", "ur").unwrap();
+        assert_eq!(rotated.__repr__(), "Fw U S'");
+    }
+
+    #[test]
+    fn ranking_by_net_length() {
+        let prefix = Algorithm::new("R U F").unwrap();
+        let solutions = vec![
+            Algorithm::new("D L").unwrap(),
+            Algorithm::new("F' U' B").unwrap(),
+        ];
+        let ranked = rank_by_net_length(&prefix, solutions);
+        assert_eq!(format!("{}", ranked[0].0 .0), "F' U' B");
+        assert_eq!(ranked[0].1, 2);
+        assert_eq!(ranked[1].1, 5);
+    }
+
+    #[test]
+    fn algorithm_is_linear() {
+        assert!(Algorithm::new("R U F").unwrap().is_linear());
+        assert!(Algorithm::new("").unwrap().is_linear());
+        assert!(!Algorithm::new("R (U F)").unwrap().is_linear());
+    }
+
+    #[test]
+    fn insertion() {
+        let solution = Algorithm::new("F R U R' U' F'").unwrap();
+        let insert = Algorithm::new("U R U' R'").unwrap();
+        let (result, change) = apply_insertion(&solution, &insert, 5).unwrap();
+        assert!(result.is_empty());
+        assert_eq!(change, -6);
+
+        let (result, change) = apply_insertion(&solution, &insert, 0).unwrap();
+        assert_eq!(result.__repr__(), "U R U' R' F R U R' U' F'");
+        assert_eq!(change, 4);
+        let (result, change) = apply_insertion(&solution, &insert, 6).unwrap();
+        assert_eq!(result.__repr__(), "F R U R' U' F' U R U' R'");
+        assert_eq!(change, 4);
+        assert!(apply_insertion(&solution, &insert, 7).is_err());
+    }
+
+    #[test]
+    fn algorithm_three_cycle() {
+        let three_cycle = |s: &str| Algorithm::new(s).unwrap().is_three_cycle();
+        assert_eq!(
+            three_cycle("R' F R' B2 R F' R' B2 R2"),
+            Some(("corner".to_string(), vec![0, 2, 1]))
+        );
+        assert_eq!(
+            three_cycle("R U' R U R U R U' R' U' R2"),
+            Some(("edge".to_string(), vec![1, 2, 3]))
+        );
+        assert_eq!(three_cycle("R U R' U'"), None);
+        assert_eq!(three_cycle("U2"), None);
+        assert_eq!(three_cycle(""), None);
+    }
+
+    #[test]
+    fn algorithm_group() {
+        let group = |s: &str| Algorithm::new(s).unwrap().group();
+        assert_eq!(group("R2 U2 F2 (D2)"), "HTR");
+        assert_eq!(group("R U R' U R U2 R'"), "2-gen");
+        assert_eq!(group("U D' U2"), "<U,D>");
+        assert_eq!(group("R L' U2 D"), "<R,L,U,D>");
+        assert_eq!(group("F"), "<F>");
+        assert_eq!(group(""), "<>");
+        assert_eq!(group("M U M' U"), "<U,M>");
+        assert_eq!(group("r U Rw'"), "<U,Rw>");
+        assert_eq!(group("M2 U2"), "HTR");
+        assert_eq!(group("R U x"), "2-gen");
+    }
+
+    #[test]
+    fn algorithm_merge_niss() {
+        let eo = Algorithm::new("F").unwrap();
+        let dr = Algorithm::new("R U").unwrap();
+        assert_eq!(eo.merge_niss(&dr, false).__repr__(), "F R U");
+        assert_eq!(eo.merge_niss(&dr, true).__repr__(), "F (R U)");
+
+        // A normal-side EO, then a DR found on the inverse of the cube after it. Both
+        // are linear so that every DR move ends up on the inverse side.
+        let linear = SolveOptions {
+            require_linear: true,
+            ..Default::default()
+        };
+        let scramble = Cube::new("R' U' F L2 D B' R2 F' U2 D' R' U' F".to_string()).unwrap();
+        let eo = StepInfo::new("eo", "fb")
+            .unwrap()
+            .solve_with(&scramble, 1, &linear)
+            .unwrap()
+            .remove(0);
+        let mut inverse = scramble.clone();
+        inverse.apply(&eo);
+        inverse.invert();
+        let dr_step = StepInfo::new("dr", "ud").unwrap();
+        let dr = dr_step.solve_with(&inverse, 1, &linear).unwrap().remove(0);
+        let mut cube = scramble.clone();
+        cube.apply(&eo.merge_niss(&dr, true).all_on_normal());
+        assert!(dr_step.is_solved(&cube).unwrap());
+    }
+
+    #[test]
+    fn algorithm_reorient() {
+        let alg = Algorithm::new("R U L (B')").unwrap();
+        assert_eq!(alg.reorient("R", "U").unwrap().__repr__(), "F U B (R')");
+        assert_eq!(alg.reorient("F", "U").unwrap().__repr__(), alg.__repr__());
+        assert!(alg.reorient("U", "D").is_err());
+        assert!(alg.reorient("F", "F").is_err());
+        let alg = Algorithm::new("r U' E").unwrap();
+        assert_eq!(alg.reorient("R", "U").unwrap().__repr__(), "f U' E");
+
+        let scramble = Algorithm::new("R U F' D2 B L'").unwrap();
+        let solution = scramble.inverted();
+        let mut cube = Cube::new("".to_string()).unwrap();
+        cube.apply(&scramble.reorient("D", "L").unwrap());
+        cube.apply(&solution.reorient("D", "L").unwrap());
+        assert!(is_fully_solved(&cube.0));
+    }
+
+    #[test]
+    fn algorithm_regrips() {
+        let regrips = |s: &str| Algorithm::new(s).unwrap().regrip_estimate();
+        assert_eq!(regrips("R U R' U R U2 R'"), 0);
+        assert_eq!(regrips("F R U R' U' F'"), 3);
+        assert_eq!(regrips("B D B' D' B2 D"), 6);
+        assert!(regrips("R U R' U'") < regrips("B' D2 B D' B"));
+        assert_eq!(regrips(""), 0);
+        assert_eq!(regrips("M' U M U2"), 0);
+        assert_eq!(regrips("R S R'"), 2);
+        assert_eq!(regrips("Fw R"), 2);
+        assert_eq!(regrips("R x R"), 1);
+    }
+
+    #[test]
+    fn algorithm_mirror() {
+        let alg = Algorithm::new("R U2 F' D (L B2 U')").unwrap();
+        let mirrored = |plane: &str| alg.mirror(plane).unwrap().__repr__();
+        assert_eq!(mirrored("m"), "L' U2 F D' (R' B2 U)");
+        assert_eq!(mirrored("s"), "R' U2 B D' (L' F2 U)");
+        assert_eq!(mirrored("e"), "R' D2 F U' (L' B2 D)");
+        for plane in ["m", "s", "e", "M"] {
+            let twice = alg.mirror(plane).unwrap().mirror(plane).unwrap();
+            assert_eq!(twice.normal_moves(), alg.normal_moves());
+            assert_eq!(twice.inverse_moves(), alg.inverse_moves());
+        }
+        assert!(alg.mirror("x").is_err());
+
+        let alg = Algorithm::new("M U Rw").unwrap();
+        assert_eq!(alg.mirror("M").unwrap().__repr__(), "M U' Lw'");
+        assert_eq!(alg.mirror("S").unwrap().__repr__(), "M' U' Rw'");
+        let cube = Cube::new(alg.__repr__()).unwrap();
+        for plane in ["M", "S", "E"] {
+            let mirrored = cube.mirror(plane).unwrap();
+            let expected = Cube::new(alg.mirror(plane).unwrap().__repr__()).unwrap();
+            assert_eq!(mirrored.edges().unwrap(), expected.edges().unwrap());
+            assert_eq!(mirrored.corners().unwrap(), expected.corners().unwrap());
+        }
+    }
+
+    #[test]
+    fn algorithm_transform() {
+        let alg = Algorithm::new("R U F' (D2 B)").unwrap();
+        let transformed = |r: &str| alg.transform(r).unwrap().__repr__();
+        assert_eq!(transformed("x"), "R B U' (F2 D)");
+        assert_eq!(transformed("x2"), "R D B' (U2 F)");
+        assert_eq!(transformed("x'"), "R F D' (B2 U)");
+        assert_eq!(transformed("y"), "F U L' (D2 R)");
+        assert_eq!(transformed("z"), "D R F' (L2 B)");
+        let back = alg.transform("y").unwrap().transform("y'").unwrap();
+        assert_eq!(back.__repr__(), alg.__repr__());
+        assert!(alg.transform("w").is_err());
+        assert!(alg.transform("x3").is_err());
+        let alg = Algorithm::new("Rw y M S").unwrap();
+        assert_eq!(alg.transform("x").unwrap().__repr__(), "Rw z' M E'");
+    }
+
+    #[test]
+    fn algorithm_metrics() {
+        let alg = Algorithm::new("R U2 F' (D2 B)").unwrap();
+        assert_eq!(alg.metric("htm").unwrap(), 5);
+        assert_eq!(alg.metric("qtm").unwrap(), 7);
+        assert_eq!(alg.metric("stm").unwrap(), 5);
+        assert_eq!(alg.metric("ETM").unwrap(), 5);
+        let empty = Algorithm::new("").unwrap();
+        for kind in ["htm", "qtm", "stm", "etm"] {
+            assert_eq!(empty.metric(kind).unwrap(), 0);
+        }
+        assert!(alg.metric("xtm").is_err());
+
+        // A slice move is two face turns but a single slice turn
+        let alg = Algorithm::new("M U").unwrap();
+        assert_eq!(alg.metric("htm").unwrap(), 3);
+        assert_eq!(alg.metric("qtm").unwrap(), 3);
+        assert_eq!(alg.metric("stm").unwrap(), 2);
+        assert_eq!(alg.metric("etm").unwrap(), 2);
+        let alg = Algorithm::new("x Rw2 (E2)").unwrap();
+        assert_eq!(alg.metric("htm").unwrap(), 3);
+        assert_eq!(alg.metric("qtm").unwrap(), 6);
+        assert_eq!(alg.metric("stm").unwrap(), 2);
+        assert_eq!(alg.metric("etm").unwrap(), 3);
+    }
+
+    #[test]
+    fn algorithm_eq_hash() {
+        let a = Algorithm::new("R U (F)").unwrap();
+        let b = Algorithm::new("R")
+            .unwrap()
+            .append("U", false)
+            .unwrap()
+            .append("F", true)
+            .unwrap();
+        assert_eq!(a.__repr__(), b.__repr__());
+        assert_eq!(a.__hash__(), b.__hash__());
+        // Same cube state, different structure
+        let linear = Algorithm::new("F' R U").unwrap();
+        assert_ne!(a.__hash__(), linear.__hash__());
+        let niss = Algorithm::new("(R) (U)").unwrap();
+        let normal = Algorithm::new("R U").unwrap();
+        assert_ne!(niss.__hash__(), normal.__hash__());
+    }
+
+    #[test]
+    fn algorithm_reverse_solution() {
+        let solution = Algorithm::new("R U (F D')").unwrap();
+        let scramble = solution.to_reverse_solution();
+        assert_eq!(scramble.__repr__(), "F D' U' R'");
+        assert!(scramble.is_linear());
+        let mut cube = Cube::new("".to_string()).unwrap();
+        cube.apply(&scramble);
+        cube.apply(&solution.all_on_normal());
+        assert!(is_fully_solved(&cube.0));
+    }
+
+    #[test]
+    fn algorithm_halves() {
+        let alg = Algorithm::new("R U2 (F' D) L").unwrap();
+        assert_eq!(alg.normal_only().__repr__(), "R U2 L");
+        assert_eq!(alg.inverse_only().__repr__(), "(F' D)");
+        let merged = alg.normal_only().merge(&alg.inverse_only());
+        assert_eq!(merged.__repr__(), alg.__repr__());
+    }
+
+    #[test]
+    fn solve_annotated_lines() {
+        let cube = Cube::new("R' U' F L2 D B' R2 F' U2 D' R' U' F".to_string()).unwrap();
+        let step = StepInfo::new("eo", "fb").unwrap();
+        let lines = step.solve_annotated(&cube, 3).unwrap();
+        let solutions = step.solve_with(&cube, 3, &SolveOptions::default()).unwrap();
+        assert_eq!(lines.len(), solutions.len());
+        for (line, alg) in lines.iter().zip(solutions) {
+            let mut c = cube.clone();
+            c.apply(&alg);
+            let expected = format!("{} // 0e ({})", alg.__repr__(), alg.len());
+            assert_eq!(*line, expected);
+            assert!(step.is_solved(&c).unwrap());
+        }
+    }
+
+    #[test]
+    fn solve_packed_round_trip() {
+        let cube = Cube::new("R' U' F L2 D B' R2 F' U2 D' R' U' F".to_string()).unwrap();
+        let step = StepInfo::new("eo", "fb").unwrap();
+        let packed = step.solve_packed(&cube, 5).unwrap();
+        let unpacked: Vec<String> = unpack_solutions(packed.clone())
+            .unwrap()
+            .iter()
+            .map(|a| a.__repr__())
+            .collect();
+        let expected: Vec<String> = step
+            .solve_with(&cube, 5, &SolveOptions::default())
+            .unwrap()
+            .iter()
+            .map(|a| a.__repr__())
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(unpacked, expected);
+        assert!(unpack_solutions(packed[..packed.len() - 1].to_vec()).is_err());
+        assert!(unpack_solutions(vec![1, 0, 18]).is_err());
+    }
+
+    #[test]
+    fn solution_spectrum_buckets() {
+        let cube = Cube::new("R' U' F L2 D B' R2 F' U2 D' R' U' F".to_string()).unwrap();
+        let step = StepInfo::new("eo", "fb").unwrap();
+        let spectrum = step.solution_spectrum(&cube, 20).unwrap();
+        let optimal = step.solve_with(&cube, 1, &SolveOptions::default()).unwrap()[0].len() as u8;
+        assert_eq!(spectrum[0].0, optimal);
+        assert!(spectrum[0].1 >= 1);
+        assert!(spectrum.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(spectrum.iter().map(|(_, n)| n).sum::<usize>() <= 20);
+    }
+
+    #[test]
+    fn solve_matching_predicate() {
+        let cube = Cube::new("R' U' F L2 D B' R2 F' U2 D' R' U' F".to_string()).unwrap();
+        let step = StepInfo::new("eo", "fb").unwrap();
+        let even = step
+            .solve_matching(&cube, 3, |alg| Ok(alg.len() % 2 == 0))
+            .unwrap();
+        assert!(!even.is_empty() && even.len() <= 3);
+        assert!(even.iter().all(|alg| alg.len() % 2 == 0));
+        let odd = step
+            .solve_matching(&cube, 3, |alg| Ok(alg.len() % 2 == 1))
+            .unwrap();
+        assert!(odd.iter().all(|alg| alg.len() % 2 == 1));
+
+        let failed = step.solve_matching(&cube, 3, |_| Err(PyValueError::new_err("boom")));
+        assert!(failed.is_err());
+    }
+
+    #[test]
+    fn solve_dedup_symmetry() {
+        // R L' is its own mirror, so its EO solutions come in symmetric pairs
+        let cube = Cube::new("R L'".to_string()).unwrap();
+        let step = StepInfo::new("eo", "rl").unwrap();
+        let solve = |dedup: bool| {
+            step.solve_with(
+                &cube,
+                6,
+                &SolveOptions {
+                    dedup_symmetry: dedup,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+        let key = |alg: &Algorithm| {
+            scramble_symmetries(&alg.0)
+                .unwrap()
+                .iter()
+                .map(|a| format!("{}", a))
+                .min()
+                .unwrap()
+        };
+        let all = solve(false);
+        let deduped = solve(true);
+        let keys: Vec<String> = deduped.iter().map(key).collect();
+        for (i, k) in keys.iter().enumerate() {
+            assert!(!keys[..i].contains(k));
+        }
+        let shortest = |algs: &Vec<Algorithm>| algs.iter().filter(|a| a.len() == 2).count();
+        assert!(shortest(&deduped) < shortest(&all));
+    }
+
+    #[test]
+    fn solution_affected_pieces() {
+        let step = StepInfo::new("eo", "fb").unwrap();
+        let cube = Cube::new("F".to_string()).unwrap();
+        let alg = Algorithm::new("F'").unwrap();
+        assert_eq!(
+            step.solution_affects(&cube, &alg),
+            (vec![2, 4, 5, 8], vec![2, 3, 4, 5])
+        );
+        let cube = Cube::new("R' U' F L2 D B' R2 F' U2 D' R' U' F".to_string()).unwrap();
+        let alg = Algorithm::new("U2").unwrap();
+        let (edges, corners) = step.solution_affects(&cube, &alg);
+        assert_eq!(edges, vec![0, 1, 2, 3]);
+        assert_eq!(corners, vec![0, 1, 2, 3]);
+        assert_eq!(
+            step.solution_affects(&cube, &Algorithm::new("").unwrap()),
+            (vec![], vec![])
+        );
+    }
+
+    #[test]
+    fn solve_dedup_keys() {
+        let cube = Cube::new("R' U' F L2 D B' R2 F' U2 D' R' U' F".to_string()).unwrap();
+        let step = StepInfo::new("eo", "fb").unwrap();
+        let count = |key: &str| step.solve_deduped_by(&cube, 20, key).unwrap().len();
+        let none = count("none");
+        assert!(count("case") <= none);
+        assert!(count("first3") <= none);
+        assert_eq!(count("case"), 1);
+        assert!(step.solve_deduped_by(&cube, 20, "xy").is_err());
+    }
+
+    #[test]
+    fn solve_include_trivial() {
+        let cube = Cube::new("R2 U F2 D' L2".to_string()).unwrap();
+        let step = StepInfo::new("dr", "ud").unwrap();
+        let solutions = step
+            .solve_with(
+                &cube,
+                3,
+                &SolveOptions {
+                    include_trivial: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(solutions[0].is_empty());
+        assert!(solutions.iter().skip(1).all(|alg| !alg.is_empty()));
+        let unsolved = Cube::new("R U R' F2".to_string()).unwrap();
+        let step = StepInfo::new("eo", "ud").unwrap();
+        let solutions = step
+            .solve_with(
+                &unsolved,
+                3,
+                &SolveOptions {
+                    include_trivial: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(solutions.iter().all(|alg| !alg.is_empty()));
+    }
+
+    #[test]
+    fn solve_canonical_both_ends() {
+        let cube = Cube::new("R' U' F L2 D B' R2 F' U2 D' R' U' F".to_string()).unwrap();
+        let step = StepInfo::new("eo", "fb").unwrap();
+        // Linear, so every solution has normal moves at both ends to check
+        let linear = SolveOptions {
+            require_linear: true,
+            ..Default::default()
+        };
+        let all = step.solve_with(&cube, 20, &linear).unwrap();
+        let canonical = step
+            .solve_with(
+                &cube,
+                20,
+                &SolveOptions {
+                    canonical_both_ends: true,
+                    ..linear
+                },
+            )
+            .unwrap();
+        assert!(!canonical.is_empty());
+        assert!(canonical.len() <= all.len());
+        for alg in canonical.iter() {
+            assert!(!alg.normal_moves().last().unwrap().ends_with('\''));
+        }
+        // Same cases either way, up to the direction of the last turn. Both searches are
+        // complete below the longest length each returned.
+        let longest = |algs: &Vec<Algorithm>| algs.iter().map(|alg| alg.len()).max().unwrap();
+        let below = longest(&all).min(longest(&canonical));
+        let cases = |algs: &Vec<Algorithm>| {
+            algs.iter()
+                .filter(|alg| alg.len() < below)
+                .map(|alg| {
+                    let mut moves = alg.normal_moves();
+                    let last = moves.pop().unwrap();
+                    moves.push(last.trim_end_matches('\'').to_string());
+                    let mut c = cube.clone();
+                    c.apply(&Algorithm::new(&moves.join(" ")).unwrap());
+                    c.coord_key()
+                })
+                .collect::<HashSet<_>>()
+        };
+        assert_eq!(cases(&canonical), cases(&all));
+    }
+
+    #[test]
+    fn solve_chaining_into_dr() {
+        let cube = Cube::new("R' U' F L2 D B' R2 F' U2 D' R' U' F".to_string()).unwrap();
+        let eo = StepInfo::new("eo", "fb").unwrap();
+        let dr = StepInfo::new("dr", "ud").unwrap();
+        let ranked = eo.solve_chaining(&cube, &dr, 3, 5).unwrap();
+        assert!(!ranked.is_empty());
+        assert!(ranked.windows(2).all(|w| w[0].1 <= w[1].1));
+        for (alg, next_len) in ranked {
+            let mut c = cube.clone();
+            c.apply(&alg);
+            let best = dr
+                .solve_with(&c, 3, &SolveOptions::default())
+                .unwrap()
+                .iter()
+                .map(|a| a.len())
+                .min()
+                .unwrap();
+            assert_eq!(next_len, best);
+        }
+    }
+
+    #[test]
+    fn solve_ergonomic_ranking() {
+        let costs: HashMap<char, f64> = DEFAULT_FACE_COSTS.iter().cloned().collect();
+        let algs = vec![
+            Algorithm::new("B D F").unwrap(),
+            Algorithm::new("R U").unwrap(),
+            Algorithm::new("B D").unwrap(),
+            Algorithm::new("R F").unwrap(),
+        ];
+        let ranked: Vec<String> = rank_by_face_cost(algs, &costs)
+            .iter()
+            .map(|a| a.__repr__())
+            .collect();
+        assert_eq!(ranked, vec!["R U", "R F", "B D", "B D F"]);
+
+        let cube = Cube::new("R' U' F L2 D B' R2 F' U2 D' R' U' F".to_string()).unwrap();
+        let step = StepInfo::new("eo", "fb").unwrap();
+        let solutions = step.solve_ergonomic(&cube, 5, None).unwrap();
+        assert!(solutions.windows(2).all(|w| w[0].len() <= w[1].len()));
+        let costs = HashMap::from([("x".to_string(), 1.0)]);
+        assert!(step.solve_ergonomic(&cube, 5, Some(costs)).is_err());
+    }
+
+    #[test]
+    fn solve_preserving_pieces() {
+        let cube = Cube::new("F".to_string()).unwrap();
+        let keeps = |s: &str| {
+            states_along(&cube.0, &Algorithm::new(s).unwrap().0)
+                .iter()
+                .all(|c| pieces_solved(c, &[11], &[7]))
+        };
+        assert!(keeps("F'"));
+        assert!(!keeps("L L' F'"));
+
+        let step = StepInfo::new("eo", "fb").unwrap();
+        let solutions = step.solve_preserving(&cube, vec![11], vec![7], 5).unwrap();
+        assert!(!solutions.is_empty());
+        for alg in solutions {
+            assert!(keeps(&alg.__repr__()));
+        }
+        assert!(step.solve_preserving(&cube, vec![2], vec![], 5).is_err());
+        assert!(step.solve_preserving(&cube, vec![12], vec![], 5).is_err());
+    }
+
+    #[test]
+    fn solve_distances() {
+        let cube = Cube::new("R' U' F L2 D B' R2 F' U2 D' R' U' F".to_string()).unwrap();
+        let step = StepInfo::new("eo", "fb").unwrap();
+        let solutions = step.solve_with_distances(&cube, 3).unwrap();
+        assert!(!solutions.is_empty());
+        for (alg, distances) in solutions.iter() {
+            assert_eq!(distances.len(), alg.len() + 1);
+            assert_eq!(*distances.last().unwrap(), 0);
+        }
+        let (alg, distances) = &solutions[0];
+        assert_eq!(distances[0] as usize, alg.len());
+        assert!(distances.windows(2).all(|w| w[1] <= w[0]));
+    }
+
+    #[test]
+    fn solve_require_linear() {
+        let cube = Cube::new("R' U' F L2 D B' R2 F' U2 D' R' U' F".to_string()).unwrap();
+        let step = StepInfo::new("eo", "fb").unwrap();
+        let solutions = step
+            .solve_with(
+                &cube,
+                10,
+                &SolveOptions {
+                    require_linear: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(!solutions.is_empty());
+        assert!(solutions.iter().all(|alg| alg.is_linear()));
+    }
+
+    #[test]
+    fn run_self_check() {
+        self_check(20, 7).unwrap();
+    }
+
+    #[test]
+    fn step_influencing_moves() {
+        let moves = influencing_moves("dr", "ud").unwrap();
+        assert_eq!(moves, vec!["F", "F'", "B", "B'", "R", "R'", "L", "L'"]);
+        let moves = influencing_moves("eo", "fb").unwrap();
+        assert_eq!(moves, vec!["F", "F'", "B", "B'"]);
+        assert!(influencing_moves("finish", "").unwrap().len() == 18);
+        assert!(influencing_moves("dr", "xy").is_err());
+    }
+
+    #[test]
+    fn trivial_scramble() {
+        let bound = |s: &str| Cube::new(s.to_string()).unwrap().solution_lower_bound();
+        assert_eq!(bound(""), 0);
+        assert_eq!(bound("R U"), 2);
+        assert_eq!(bound("R U R' U'"), 4);
+        // Past the exact search, the bound comes from the pruning tables
+        let scramble = "R' U' F L2 D B' R2 F' U2 D' R' U' F";
+        assert_eq!(bound(scramble), 7);
+        assert_eq!(bound(WELL_KNOWN_POSITIONS[1].1), 8);
+        assert!(is_trivial_scramble("", 1).unwrap());
+        assert!(!is_trivial_scramble("", 0).unwrap());
+        assert!(is_trivial_scramble("R U R'", 4).unwrap());
+        assert!(!is_trivial_scramble("R U R'", 3).unwrap());
+        assert!(!is_trivial_scramble(scramble, 7).unwrap());
+        assert!(!is_trivial_scramble(WELL_KNOWN_POSITIONS[1].1, 8).unwrap());
+        assert!(is_trivial_scramble(scramble, 11).is_err());
+    }
+
+    #[test]
+    fn batch_recognition() {
+        let cubes: Vec<Cube> = ["", "R", "R U", "R U F", "R2 U F2 D' L", "U R2 F2 D"]
+            .iter()
+            .map(|s| Cube::new(s.to_string()).unwrap())
+            .collect();
+        let step = StepInfo::new("dr", "ud").unwrap();
+        let names = recognize_batch("dr", "ud", cubes.clone()).unwrap();
+        assert_eq!(names.len(), cubes.len());
+        for (cube, name) in cubes.iter().zip(names.iter()) {
+            assert_eq!(&step.case_name(cube).unwrap(), name);
+        }
+        assert!(recognize_batch("dr", "xy", cubes).is_err());
+        assert!(recognize_batch("eo", "fb", vec![]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn smart_solve_next_step() {
+        let cube = Cube::new("R U F".to_string()).unwrap();
+        let solved_after = |alg: &Algorithm, kind: &str, variant: &str| {
+            let mut c = cube.clone();
+            c.apply(&alg.all_on_normal());
+            StepInfo::new(kind, variant).unwrap().is_solved(&c).unwrap()
+        };
+        let algs = smart_solve(&cube, 2, None).unwrap();
+        assert!(!algs.is_empty());
+        assert!(algs.iter().all(|a| solved_after(a, "eo", "fb")));
+
+        let algs = smart_solve(&cube, 2, Some("EO[ud] > DR")).unwrap();
+        assert!(!algs.is_empty());
+        assert!(algs.iter().all(|a| solved_after(a, "eo", "ud")));
+
+        // EO is already solved, so the plan moves on to DR
+        let cube = Cube::new("F R".to_string()).unwrap();
+        let algs = smart_solve(&cube, 1, Some("EO[ud] > DR[fb]")).unwrap();
+        let mut c = cube.clone();
+        c.apply(&algs[0].all_on_normal());
+        assert!(StepInfo::new("dr", "fb").unwrap().is_solved(&c).unwrap());
+
+        let solved = Cube::new("".to_string()).unwrap();
+        assert!(smart_solve(&solved, 1, None).unwrap().is_empty());
+        assert!(smart_solve(&solved, 1, Some("EO > DR")).unwrap().is_empty());
+        assert!(smart_solve(&cube, 1, Some("XX")).is_err());
+    }
+
+    #[test]
+    fn orientation_vectors() {
+        let cube = Cube::new("R' U' F L2 D B' R2 F' U2 D' R' U' F".to_string()).unwrap();
+        let edges = cube.edge_orientation_vector("ud").unwrap();
+        let bad = edges.iter().filter(|o| !**o).count();
+        assert_eq!(bad, cube.0.count_bad_edges_ud() as usize);
+        assert_eq!(edges.iter().all(|o| *o), cube.is_eo("ud").unwrap());
+
+        // F twists the F-layer corners off the UD axis but keeps FB orientation
+        let cube = Cube::new("F".to_string()).unwrap();
+        assert!(cube
+            .corner_orientation_vector("fb")
+            .unwrap()
+            .iter()
+            .all(|o| *o));
+        assert_eq!(
+            cube.corner_orientation_vector("ud")
+                .unwrap()
+                .iter()
+                .filter(|o| !**o)
+                .count(),
+            4
+        );
+        assert!(cube.edge_orientation_vector("xy").is_err());
+        assert!(cube.corner_orientation_vector("xy").is_err());
+    }
+
+    #[test]
+    fn facelets_round_trip() {
+        let solved = Cube::new("".to_string()).unwrap();
+        assert_eq!(
+            solved.facelets().unwrap(),
+            "UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB"
+        );
+        let scramble = Algorithm::new("R' U' F L2 D B' R2 F' U2 D' R' U' F").unwrap();
+        let mut cube = Cube::new(scramble.__repr__()).unwrap();
+        assert_ne!(cube.facelets().unwrap(), solved.facelets().unwrap());
+        cube = from_facelets(&cube.facelets().unwrap()).unwrap();
+        cube.apply(&scramble.inverted());
+        assert_eq!(cube.facelets().unwrap(), solved.facelets().unwrap());
+    }
+
+    #[test]
+    fn cube_transform() {
+        let scrambled = Cube::new("F R2 U2 L2 B'".to_string()).unwrap();
+        assert!(scrambled.is_dr("fb").unwrap());
+        let mut cube = scrambled.copy();
+        cube.transform("x").unwrap();
+        assert!(cube.is_dr("ud").unwrap());
+        cube.transform("x'").unwrap();
+        assert_eq!(cube.coord_key(), scrambled.coord_key());
+
+        let mut twice = scrambled.copy();
+        twice.transform("y").unwrap();
+        twice.transform("y").unwrap();
+        let mut half = scrambled.copy();
+        half.transform("y2").unwrap();
+        assert_eq!(twice.coord_key(), half.coord_key());
+        half.transform("y2").unwrap();
+        assert_eq!(half.coord_key(), scrambled.coord_key());
+
+        assert!(cube.transform("w").is_err());
+        assert!(cube.transform("x3").is_err());
+        assert!(cube.transform("").is_err());
+    }
+
+    #[test]
+    fn cube_random_seeded() {
+        assert_eq!(
+            Cube::random(Some(7)).coord_key(),
+            Cube::random(Some(7)).coord_key()
+        );
+        assert_ne!(
+            Cube::random(Some(7)).coord_key(),
+            Cube::random(Some(8)).coord_key()
+        );
+        assert!(Cube::random(None).validity_error().is_none());
+    }
+
+    #[test]
+    fn cube_pickle_state() {
+        let cube = Cube::new("R' U' F L2 D B' R2 F' U2 D' R' U' F".to_string()).unwrap();
+        let mut restored = Cube::new("".to_string()).unwrap();
+        restored.__setstate__(cube.__getstate__()).unwrap();
+        assert_eq!(restored.coord_key(), cube.coord_key());
+        assert!(Cube::new("".to_string()).unwrap().__getstate__().is_empty());
+
+        assert!(restored.__setstate__(vec![0, 1]).is_err());
+        assert!(restored.__setstate__(vec![0, 12, 7]).is_err());
+        // A single flipped edge can not be reached
+        assert!(restored.__setstate__(vec![0, 0, 0]).is_err());
+        assert_eq!(restored.coord_key(), cube.coord_key());
+    }
+
+    #[test]
+    fn algorithm_pickle_state() {
+        let mut alg = Algorithm::new("R U2 (F' D)").unwrap();
+        alg.set_comment(Some("EO".to_string()));
+        let mut restored = Algorithm::new("").unwrap();
+        restored.__setstate__(alg.__getstate__()).unwrap();
+        assert_eq!(restored.__repr__(), "R U2 (F' D) // EO");
+        let bad = (vec!["R".to_string(), "Q".to_string()], vec![], None);
+        assert!(restored.__setstate__(bad).is_err());
+    }
+
+    #[test]
+    fn cube_copy() {
+        let cube = Cube::new("R U".to_string()).unwrap();
+        let mut copy = cube.copy();
+        assert_eq!(copy.coord_key(), cube.coord_key());
+        copy.apply(&Algorithm::new("F").unwrap());
+        assert_ne!(copy.coord_key(), cube.coord_key());
+        assert_eq!(
+            cube.coord_key(),
+            Cube::new("R U".to_string()).unwrap().coord_key()
+        );
+    }
+
+    #[test]
+    fn cube_hash() {
+        let a = Cube::new("R U R' U'".to_string()).unwrap();
+        // R U R' U' has order 6, so seven repetitions reach the same state
+        let b = Cube::new(vec!["R U R' U'"; 7].join(" ")).unwrap();
+        assert_eq!(a.coord_key(), b.coord_key());
+        assert_eq!(a.__hash__(), b.__hash__());
+        let c = Cube::new("R U".to_string()).unwrap();
+        assert_ne!(a.__hash__(), c.__hash__());
+    }
+
+    #[test]
+    fn cube_from_facelets() {
+        let cube = Cube::new("R' U' F L2 D B' R2 F' U2 D' R' U' F".to_string()).unwrap();
+        let read = Cube::parse_facelets(&cube.facelets().unwrap()).unwrap();
+        assert_eq!(read.coord_key(), cube.coord_key());
+
+        let solved = "UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB";
+        let with = |changes: &[(usize, char)]| {
+            let mut letters: Vec<char> = solved.chars().collect();
+            for (i, c) in changes {
+                letters[*i] = *c;
+            }
+            letters.into_iter().collect::<String>()
+        };
+        // The UF edge flipped in place
+        assert!(Cube::parse_facelets(&with(&[(7, 'F'), (19, 'U')])).is_err());
+        // The UFR corner twisted in place
+        assert!(Cube::parse_facelets(&with(&[(8, 'F'), (20, 'R'), (9, 'U')])).is_err());
+        assert!(Cube::parse_facelets(&with(&[(0, 'X')])).is_err());
+        assert!(Cube::parse_facelets(&solved[1..]).is_err());
+    }
+
+    #[test]
+    fn furthest_step_reached() {
+        let furthest = |s: &str| furthest_step(&Cube::new(s.to_string()).unwrap()).unwrap();
+        let step = |k: &str, v: &str| (k.to_string(), v.to_string());
+        assert_eq!(furthest("R U F"), step("", ""));
+        assert_eq!(furthest("F R"), step("eo", "ud"));
+        assert_eq!(furthest("R U R' F2"), step("eo", "fb"));
+        assert_eq!(furthest("R2 U F2 D'"), step("dr", "ud"));
+        assert_eq!(furthest("F R2 U2 L2 B'"), step("dr", "fb"));
+        assert_eq!(furthest(""), step("finish", ""));
+
+        let cube = Cube::new("R2 U2 F2".to_string()).unwrap();
+        let (kind, variant) = furthest_step(&cube).unwrap();
+        assert!(kind == "htr" || kind == "fr");
+        assert!(StepInfo::new(&kind, &variant)
+            .unwrap()
+            .is_solved(&cube)
+            .unwrap());
+    }
+
+    #[test]
+    fn insertions_step() {
+        let cube = Cube::new("R U R' U R U2 R'".to_string()).unwrap();
+        let insertions = StepInfo::new("insertions", "").unwrap();
+        let finish = StepInfo::new("finish", "").unwrap();
+        assert!(insertions.step().is_ok());
+        assert_eq!(
+            insertions.edge_visibility(&cube).unwrap(),
+            finish.edge_visibility(&cube).unwrap()
+        );
+        assert_eq!(
+            insertions.corner_visibility(&cube).unwrap(),
+            finish.corner_visibility(&cube).unwrap()
+        );
+        assert!(!insertions.is_solved(&cube).unwrap());
+    }
+
+    #[test]
+    fn step_variations() {
+        let cube = Cube::new("R' U' F L2 D B' R2 F' U2 D' R' U' F".to_string()).unwrap();
+        let step = StepInfo::new("eo", "fb").unwrap();
+        for alg in step.solve_with(&cube, 5, &SolveOptions::default()).unwrap() {
+            for variation in step.variations(&cube, &alg).unwrap() {
+                assert_ne!(variation.__repr__(), alg.__repr__());
+                let mut c = cube.clone();
+                c.apply(&variation);
+                assert!(step.is_solved(&c).unwrap());
+            }
+        }
+        let cube = Cube::new("F".to_string()).unwrap();
+        let variations = step
+            .variations(&cube, &Algorithm::new("F'").unwrap())
+            .unwrap();
+        let variations: Vec<String> = variations.iter().map(|a| a.__repr__()).collect();
+        assert!(variations.contains(&"F".to_string()));
+    }
+
+    #[test]
+    fn step_fast_case() {
+        let fast_case = |variant: &str, s: &str| {
+            StepInfo::new("eo", variant)
+                .unwrap()
+                .fast_case(&Cube::new(s.to_string()).unwrap())
+                .unwrap()
+        };
+        assert_eq!(fast_case("ud", "U"), Some("4-flip layer".to_string()));
+        assert_eq!(fast_case("fb", "F"), Some("4-flip layer".to_string()));
+        assert_eq!(fast_case("rl", "R"), Some("4-flip layer".to_string()));
+        assert_eq!(fast_case("fb", "U"), None);
+        let cube = Cube::new("R".to_string()).unwrap();
+        assert!(StepInfo::new("dr", "ud").unwrap().fast_case(&cube).is_err());
+    }
+
+    #[test]
+    fn step_solve_cycle_info() {
+        let cube = Cube::new("R' F R' B2 R F' R' B2 R2".to_string()).unwrap();
+        let finish = StepInfo::new("finish", "").unwrap();
+        let solutions = finish.solve_cycle_info(&cube, 2).unwrap();
+        assert!(!solutions.is_empty());
+        assert!(solutions
+            .iter()
+            .all(|(_, info)| info == "solves UBL-UFR-UBR corner cycle"));
+        let eo = StepInfo::new("eo", "fb").unwrap();
+        assert!(eo.solve_cycle_info(&cube, 2).is_err());
+    }
+
+    #[test]
+    fn prune_dominated_solutions() {
+        let cube = Cube::new("F".to_string()).unwrap();
+        let algs = vec![
+            Algorithm::new("F'").unwrap(),
+            Algorithm::new("F' R U").unwrap(),
+            Algorithm::new("F").unwrap(),
+        ];
+        let pruned = prune_dominated("eo", "fb", &cube, algs).unwrap();
+        let pruned: Vec<String> = pruned.iter().map(|a| a.__repr__()).collect();
+        assert_eq!(pruned, vec!["F'", "F"]);
+    }
+
+    #[test]
+    fn solve_plan_iterator() {
+        let scramble = "R' U' F L2 D B' R2 F' U2 D' R' U' F";
+        let mut solutions = solve_plan_iter(scramble, "EO > DR").unwrap();
+        for _ in 0..2 {
+            let solution = solutions.__next__().unwrap();
+            assert_eq!(solution.steps.len(), 2);
+            let mut cube = Cube::new(scramble.to_string()).unwrap();
+            for alg in solution.algs.iter() {
+                cube.apply(alg);
+            }
+            assert!(StepInfo::new("dr", &solution.steps[1].variant)
+                .unwrap()
+                .is_solved(&cube)
+                .unwrap());
+        }
+        assert!(solve_plan_iter(scramble, "DR > EO").is_err());
+    }
+
+    #[test]
+    fn solve_statistics() {
+        let json = solve_stats(3, "EO > DR", 42).unwrap();
+        assert!(json.starts_with("{\"n\": 3, \"steps\": {\"eo\": {\"mean\": "));
+        assert!(json.contains("\"dr\": {\"mean\": "));
+        for key in ["\"median\": ", "\"min\": ", "\"max\": "] {
+            assert_eq!(json.matches(key).count(), 2);
+        }
+        assert_eq!(json, solve_stats(3, "EO > DR", 42).unwrap());
+        assert!(solve_stats(0, "EO > DR", 42).is_err());
+        assert!(solve_stats(3, "DR > EO", 42).is_err());
+    }
+
     #[test]
     fn scramble_gen() {
-        let s = scramble().unwrap();
+        let s = scramble(None, None, None).unwrap();
         assert!(s.len() > 0);
     }
 
+    #[test]
+    fn scramble_inversion() {
+        let s = "R U' F2 (B D)";
+        let inverted = invert_scramble(s).unwrap();
+        assert_eq!(inverted, "B D F2 U R'");
+        assert_eq!(invert_scramble(&inverted).unwrap(), "R U' F2 D' B'");
+        assert_eq!(invert_scramble("x R U").unwrap(), "F' R'");
+        assert_eq!(invert_scramble("Rw U").unwrap(), "F' L'");
+        assert!(invert_scramble("R U Q").is_err());
+    }
+
+    #[test]
+    fn cube_scramble_string() {
+        let cube = Cube::new("R U' F2 D B L' U2".to_string()).unwrap();
+        let s = cube.scramble_string().unwrap();
+        let rebuilt = Cube::new(s).unwrap();
+        assert_eq!(rebuilt.edges().unwrap(), cube.edges().unwrap());
+        assert_eq!(rebuilt.corners().unwrap(), cube.corners().unwrap());
+    }
+
+    #[test]
+    fn cube_apply_on_inverse() {
+        let mut cube = Cube::new("R U F".to_string()).unwrap();
+        cube.apply_alg_on_inverse_string("D L' B2").unwrap();
+        let mut expected = Cube::new("R U F".to_string()).unwrap();
+        expected.apply(&Algorithm::new("(D L' B2)").unwrap());
+        assert_eq!(cube.edges().unwrap(), expected.edges().unwrap());
+        assert_eq!(cube.corners().unwrap(), expected.corners().unwrap());
+        assert!(cube.apply_alg_on_inverse_string("D Q").is_err());
+        cube.apply_alg_on_inverse_string("M Rw").unwrap();
+        expected.apply(&Algorithm::new("(M Rw)").unwrap());
+        assert_eq!(cube.edges().unwrap(), expected.edges().unwrap());
+        assert_eq!(cube.corners().unwrap(), expected.corners().unwrap());
+    }
+
+    #[test]
+    fn cube_well_known_name() {
+        let superflip =
+            Cube::new("U R2 F B R B2 R U2 L B2 R U' D' R2 F R' L B2 U2 F2".to_string()).unwrap();
+        assert_eq!(superflip.well_known_name(), Some("superflip".to_string()));
+        let edges = superflip.0.edges.get_edges();
+        assert!(edges
+            .iter()
+            .enumerate()
+            .all(|(pos, e)| e.id as usize == pos && !e.oriented_fb));
+        let checkerboard = Cube::new("R2 L2 U2 D2 F2 B2".to_string()).unwrap();
+        assert_eq!(
+            checkerboard.well_known_name(),
+            Some("checkerboard".to_string())
+        );
+        assert_eq!(
+            Cube::new("".to_string()).unwrap().well_known_name(),
+            Some("solved".to_string())
+        );
+        assert_eq!(
+            Cube::new("R U".to_string()).unwrap().well_known_name(),
+            None
+        );
+    }
+
+    #[test]
+    fn cube_delta() {
+        let a = Cube::new("R U' F2 D B L' U2".to_string()).unwrap();
+        let b = Cube::new("R U' F2 D B L' U2 R2 F".to_string()).unwrap();
+        let delta = a.delta_to(&b);
+        assert!(delta.len() < 3 * 20);
+        let mut c = a.clone();
+        c.apply_delta(delta).unwrap();
+        assert_eq!(c.edges().unwrap(), b.edges().unwrap());
+        assert_eq!(c.corners().unwrap(), b.corners().unwrap());
+        c.apply_delta(b.delta_to(&a)).unwrap();
+        assert_eq!(c.edges().unwrap(), a.edges().unwrap());
+        assert_eq!(c.corners().unwrap(), a.corners().unwrap());
+        assert!(a.delta_to(&a).is_empty());
+        assert!(c.apply_delta(vec![0, 1]).is_err());
+        assert!(c.apply_delta(vec![20, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn cube_subgroup_checks() {
+        let mut cube = Cube::new("R' U' F L2 D B' R2 F' U2 D' R' U' F".to_string()).unwrap();
+        assert!(!cube.is_eo("fb").unwrap());
+        assert!(!cube.is_dr("ud").unwrap() && !cube.is_htr() && !cube.is_fr("ud").unwrap());
+        let stages = [("eo", "fb"), ("dr", "ud"), ("htr", "ud"), ("fr", "ud")];
+        for (kind, variant) in stages {
+            let step = StepInfo::new(kind, variant).unwrap();
+            let alg = step
+                .solve_with(&cube, 1, &SolveOptions::default())
+                .unwrap()
+                .remove(0);
+            cube.apply(&alg);
+            assert!(cube.is_eo("fb").unwrap());
+            assert!(kind == "eo" || cube.is_dr("ud").unwrap());
+            assert!(kind == "eo" || kind == "dr" || cube.is_htr());
+        }
+        assert!(cube.is_fr("ud").unwrap());
+        assert!(cube.is_eo("ud").unwrap() && cube.is_eo("rl").unwrap());
+        assert!(cube.is_dr("fb").unwrap() && cube.is_dr("rl").unwrap());
+        assert!(cube.is_eo("xy").is_err());
+        assert!(cube.is_dr("").is_err());
+    }
+
+    #[test]
+    fn cube_auf_to_solve() {
+        let auf = |s: &str| Cube::new(s.to_string()).unwrap().auf_to_solve();
+        assert_eq!(auf("U2"), Some("U2".to_string()));
+        assert_eq!(auf("U"), Some("U'".to_string()));
+        assert_eq!(auf("D'"), Some("D".to_string()));
+        assert_eq!(auf(""), None);
+        assert_eq!(auf("R U"), None);
+    }
+
+    #[test]
+    fn cube_mirror() {
+        let scramble = "R U' F2 D B L' U2";
+        let cube = Cube::new(scramble.to_string()).unwrap();
+        for plane in ["M", "S", "E"] {
+            let mirrored = cube.mirror(plane).unwrap();
+            let twice = mirrored.mirror(plane).unwrap();
+            assert_eq!(twice.edges().unwrap(), cube.edges().unwrap());
+            assert_eq!(twice.corners().unwrap(), cube.corners().unwrap());
+
+            let alg = Algorithm::new(scramble).unwrap().mirror(plane).unwrap();
+            let expected = Cube::new(alg.__repr__()).unwrap();
+            assert_eq!(mirrored.edges().unwrap(), expected.edges().unwrap());
+            assert_eq!(mirrored.corners().unwrap(), expected.corners().unwrap());
+
+            let mut solved = mirrored.clone();
+            solved.apply(
+                &Algorithm::new(scramble)
+                    .unwrap()
+                    .inverted()
+                    .mirror(plane)
+                    .unwrap(),
+            );
+            assert!(Finish.is_solved(&solved.0));
+        }
+        assert!(cube.mirror("x").is_err());
+    }
+
+    #[test]
+    fn cube_validity() {
+        let cube = Cube::new("R U' F2 D B L' U2".to_string()).unwrap();
+        assert!(cube.is_valid());
+        assert_eq!(cube.validity_error(), None);
+
+        let mut edges = cube.0.edges.get_edges();
+        edges[0].oriented_ud = !edges[0].oriented_ud;
+        edges[0].oriented_fb = !edges[0].oriented_fb;
+        edges[0].oriented_rl = !edges[0].oriented_rl;
+        let flipped = Cube(Cube333::new(EdgeCube333::new(edges), cube.0.corners));
+        assert_eq!(flipped.validity_error(), Some("Flipped edge".to_string()));
+
+        let mut corners = cube.0.corners.get_corners();
+        corners[0].orientation = (corners[0].orientation + 1) % 3;
+        let twisted = Cube(Cube333::new(cube.0.edges, CornerCube333::new(corners)));
+        assert_eq!(twisted.validity_error(), Some("Twisted corner".to_string()));
+
+        let mut corners = cube.0.corners.get_corners();
+        corners.swap(0, 1);
+        let swapped = Cube(Cube333::new(cube.0.edges, CornerCube333::new(corners)));
+        assert!(!swapped.is_valid());
+        assert_eq!(swapped.validity_error(), Some("Swapped pieces".to_string()));
+    }
+
     #[test]
     fn test_visibility() {
         let cube = Cube::new("R U F".to_string()).unwrap();
@@ -631,4 +3928,41 @@ mod tests {
         let visibility = s.edge_visibility(&cube).unwrap();
         assert!(visibility[0].0 & BadFace as u8 > 0);
     }
+
+    #[test]
+    fn test_describe_many_frames() {
+        let steps = [
+            ("eo", "ud"),
+            ("dr", "fb"),
+            ("htr", "rl"),
+            ("fr", "ud"),
+            ("slice", "ud"),
+            ("finish", ""),
+        ];
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut cube = Cube::new("".to_string()).unwrap();
+        for _ in 0..200 {
+            let turn = FACE_TURNS[rng.random_range(0..FACE_TURNS.len())];
+            cube.apply(&Algorithm::new(turn).unwrap());
+            for (kind, variant) in steps {
+                let s = StepInfo::new(kind, variant).unwrap();
+                let (edges, corners) = s.describe(&cube).unwrap();
+                assert_eq!(edges, s.edge_visibility(&cube).unwrap());
+                assert_eq!(corners, s.corner_visibility(&cube).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_scrambled_visibility() {
+        let cube = Cube::new("R U F".to_string()).unwrap();
+        let s = StepInfo::new("", "").unwrap();
+        let any = Any as u8;
+        let edges = s.edge_visibility(&cube).unwrap();
+        assert_eq!(edges, vec![(any, any); 12]);
+        let corners = s.corner_visibility(&cube).unwrap();
+        assert_eq!(corners, vec![(any, any, any); 8]);
+        assert!(s.is_solved(&cube).unwrap());
+        assert_eq!(s.case_name(&cube).unwrap(), "");
+    }
 }
@@ -0,0 +1,594 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use cubelib::algs::Algorithm as LibAlgorithm;
+use cubelib::cube::turn::Invertible;
+use cubelib::cube::Turn333;
+
+// Faces around each face, clockwise starting from the top. Same as orientation.py
+fn axis_rotation(face: char) -> [char; 4] {
+    match face {
+        'F' => ['U', 'L', 'D', 'R'],
+        'B' => ['U', 'R', 'D', 'L'],
+        'R' => ['U', 'F', 'D', 'B'],
+        'L' => ['U', 'B', 'D', 'F'],
+        'U' => ['F', 'R', 'B', 'L'],
+        _ => ['F', 'L', 'B', 'R'],
+    }
+}
+
+fn opposite(face: char) -> char {
+    match face {
+        'U' => 'D',
+        'D' => 'U',
+        'F' => 'B',
+        'B' => 'F',
+        'R' => 'L',
+        _ => 'R',
+    }
+}
+
+/// The faces of the cube seen on top and in front, e.g. "uf" for the default view
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Orientation {
+    pub top: char,
+    pub front: char,
+}
+
+impl FromStr for Orientation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let faces: Vec<char> = s.trim().to_uppercase().chars().collect();
+        match faces[..] {
+            [top, front]
+                if "UDFBRL".contains(top)
+                    && "UDFBRL".contains(front)
+                    && front != top
+                    && front != opposite(top) =>
+            {
+                Ok(Orientation { top, front })
+            }
+            _ => Err(format!("Invalid orientation: {}", s)),
+        }
+    }
+}
+
+impl Orientation {
+    pub fn right(&self) -> char {
+        let rot = axis_rotation(self.top);
+        let i = rot.iter().position(|f| *f == self.front).unwrap();
+        rot[(i + 1) % 4]
+    }
+
+    /// The face of the cube that is in the given position in this view
+    pub fn absolute(&self, relative: char) -> char {
+        match relative {
+            'U' => self.top,
+            'D' => opposite(self.top),
+            'F' => self.front,
+            'B' => opposite(self.front),
+            'R' => self.right(),
+            _ => opposite(self.right()),
+        }
+    }
+
+    /// The view after a clockwise x, y or z rotation
+    pub fn rotated(&self, axis: char) -> Orientation {
+        match axis {
+            'x' => Orientation {
+                top: self.absolute('F'),
+                front: self.absolute('D'),
+            },
+            'y' => Orientation {
+                top: self.top,
+                front: self.absolute('R'),
+            },
+            _ => Orientation {
+                top: self.absolute('L'),
+                front: self.front,
+            },
+        }
+    }
+
+    /// The position of the given face of the cube in this view
+    pub fn relative(&self, absolute: char) -> char {
+        "UDFBRL"
+            .chars()
+            .find(|r| self.absolute(*r) == absolute)
+            .unwrap()
+    }
+}
+
+/// Rewrites every move of the algorithm with the face given by `f`
+pub fn relabel<F: Fn(char) -> char>(alg: &LibAlgorithm, f: F) -> Result<LibAlgorithm, String> {
+    let map = |moves: &Vec<Turn333>| {
+        moves
+            .iter()
+            .map(|t| {
+                let s = format!("{}", t);
+                let mut chars = s.chars();
+                let face = chars.next().unwrap();
+                let s = format!("{}{}", f(face), chars.as_str());
+                Turn333::from_str(&s).map_err(|_| format!("Invalid move: {}", s))
+            })
+            .collect::<Result<Vec<Turn333>, String>>()
+    };
+    Ok(LibAlgorithm {
+        normal_moves: map(&alg.normal_moves)?,
+        inverse_moves: map(&alg.inverse_moves)?,
+    })
+}
+
+// Face turns and rotation for each wide or slice move, clockwise
+fn expand_move(m: &str) -> Option<(&'static str, char)> {
+    match m {
+        "Rw" | "r" => Some(("L", 'x')),
+        "Lw" | "l" => Some(("R", 'X')),
+        "Uw" | "u" => Some(("D", 'y')),
+        "Dw" | "d" => Some(("U", 'Y')),
+        "Fw" | "f" => Some(("B", 'z')),
+        "Bw" | "b" => Some(("F", 'Z')),
+        "M" => Some(("L' R", 'X')),
+        "E" => Some(("D' U", 'Y')),
+        "S" => Some(("F' B", 'z')),
+        _ => None,
+    }
+}
+
+// The face each slice move and rotation turns like, slices first
+const TURNS_LIKE: [(&str, char); 6] = [
+    ("M", 'L'),
+    ("E", 'D'),
+    ("S", 'F'),
+    ("x", 'R'),
+    ("y", 'U'),
+    ("z", 'F'),
+];
+
+// Every layer a move can turn, as written, with the axis it turns around
+const LAYERS: [(&str, char); 24] = [
+    ("U", 'y'),
+    ("D", 'y'),
+    ("F", 'z'),
+    ("B", 'z'),
+    ("R", 'x'),
+    ("L", 'x'),
+    ("Uw", 'y'),
+    ("Dw", 'y'),
+    ("Fw", 'z'),
+    ("Bw", 'z'),
+    ("Rw", 'x'),
+    ("Lw", 'x'),
+    ("u", 'y'),
+    ("d", 'y'),
+    ("f", 'z'),
+    ("b", 'z'),
+    ("r", 'x'),
+    ("l", 'x'),
+    ("M", 'x'),
+    ("E", 'y'),
+    ("S", 'z'),
+    ("x", 'x'),
+    ("y", 'y'),
+    ("z", 'z'),
+];
+
+/// A move as written: a face turn, wide move ("Rw" or "r"), slice move or rotation,
+/// turned clockwise `quarters` times
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Move {
+    pub layer: &'static str,
+    pub quarters: u8,
+}
+
+impl FromStr for Move {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let split = s
+            .find(|c| c == '2' || c == '\'')
+            .filter(|i| *i > 0)
+            .unwrap_or(s.len());
+        let (m, suffix) = s.split_at(split);
+        let quarters = match suffix {
+            "" => 1,
+            "2" | "2'" => 2,
+            "'" => 3,
+            _ => return Err(format!("Invalid move: {}", s)),
+        };
+        match LAYERS.iter().find(|(layer, _)| *layer == m) {
+            Some((layer, _)) => Ok(Move {
+                layer: *layer,
+                quarters,
+            }),
+            None if m.starts_with(|c: char| c.is_ascii_digit()) => {
+                Err(format!("Not a 3x3 move: {}", s))
+            }
+            None => Err(format!("Invalid move: {}", s)),
+        }
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            self.layer,
+            ["", "", "2", "'"][self.quarters as usize]
+        )
+    }
+}
+
+impl Move {
+    pub fn axis(&self) -> char {
+        LAYERS
+            .iter()
+            .find(|(layer, _)| *layer == self.layer)
+            .unwrap()
+            .1
+    }
+
+    pub fn is_face_turn(&self) -> bool {
+        "UDFBRL".contains(self.layer) && self.layer.len() == 1
+    }
+
+    pub fn is_slice(&self) -> bool {
+        matches!(self.layer, "M" | "E" | "S")
+    }
+
+    pub fn is_rotation(&self) -> bool {
+        matches!(self.layer, "x" | "y" | "z")
+    }
+
+    // True if both moves turn the same layers, "Rw" and "r" being two names for one
+    fn same_layers(&self, other: &Move) -> bool {
+        let wide = |layer: &str| match layer {
+            "u" | "d" | "f" | "b" | "r" | "l" => format!("{}w", layer.to_uppercase()),
+            _ => layer.to_string(),
+        };
+        wide(self.layer) == wide(other.layer)
+    }
+
+    pub fn inverted(&self) -> Move {
+        Move {
+            layer: self.layer,
+            quarters: 4 - self.quarters,
+        }
+    }
+
+    /// The same turn with every face renamed by `f`, as after a rotation. A slice move
+    /// or rotation becomes the one on the new axis, inverted when it lands on the face
+    /// opposite the one it turns like: "M" with L renamed to R is "M'".
+    pub fn relabeled<F: Fn(char) -> char>(&self, f: F) -> Move {
+        let turns_like = TURNS_LIKE
+            .iter()
+            .copied()
+            .find(|(layer, _)| *layer == self.layer);
+        let (layer, flipped) = match turns_like {
+            Some((_, face)) => {
+                let to = f(face);
+                let skip = if self.is_rotation() { 3 } else { 0 };
+                let (layer, face) = TURNS_LIKE
+                    .iter()
+                    .copied()
+                    .skip(skip)
+                    .take(3)
+                    .find(|(_, face)| *face == to || *face == opposite(to))
+                    .unwrap();
+                (layer, face != to)
+            }
+            None => {
+                let mut chars = self.layer.chars();
+                let face = chars.next().unwrap();
+                let to = f(face.to_ascii_uppercase());
+                let to = if face.is_ascii_lowercase() {
+                    to.to_ascii_lowercase()
+                } else {
+                    to
+                };
+                let name = format!("{}{}", to, chars.as_str());
+                // Every face renames to a face, so the layer exists
+                (Move::from_str(&name).unwrap().layer, false)
+            }
+        };
+        let m = Move {
+            layer,
+            quarters: self.quarters,
+        };
+        if flipped {
+            m.inverted()
+        } else {
+            m
+        }
+    }
+}
+
+/// The moves of an algorithm as written, normal side first
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Notation {
+    pub normal: Vec<Move>,
+    pub inverse: Vec<Move>,
+}
+
+impl FromStr for Notation {
+    type Err = String;
+
+    /// Moves separated by spaces, with the inverse moves in parentheses, e.g.
+    /// "M U (Rw')". Each move is added with `push`, so repeated layers cancel.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut moves = Notation::default();
+        let mut inverse = false;
+        for token in s.replace('(', " ( ").replace(')', " ) ").split_whitespace() {
+            match token {
+                "(" if !inverse => inverse = true,
+                ")" if inverse => inverse = false,
+                "(" | ")" => return Err(format!("Unbalanced parentheses: {}", s)),
+                _ => moves.push(Move::from_str(token)?, inverse),
+            }
+        }
+        if inverse {
+            return Err(format!("Unbalanced parentheses: {}", s));
+        }
+        Ok(moves)
+    }
+}
+
+impl Display for Notation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let join = |moves: &[Move]| {
+            moves
+                .iter()
+                .map(|m| m.to_string())
+                .collect::<Vec<String>>()
+                .join(" ")
+        };
+        match (self.normal.is_empty(), self.inverse.is_empty()) {
+            (_, true) => write!(f, "{}", join(&self.normal)),
+            (true, false) => write!(f, "({})", join(&self.inverse)),
+            (false, false) => write!(f, "{} ({})", join(&self.normal), join(&self.inverse)),
+        }
+    }
+}
+
+impl Notation {
+    /// The notation of an algorithm of face turns, move for move
+    pub fn from_alg(alg: &LibAlgorithm) -> Notation {
+        let moves = |turns: &Vec<Turn333>| {
+            turns
+                .iter()
+                .map(|t| Move::from_str(&format!("{}", t)).unwrap())
+                .collect()
+        };
+        Notation {
+            normal: moves(&alg.normal_moves),
+            inverse: moves(&alg.inverse_moves),
+        }
+    }
+
+    /// Appends a move to one side, cancelling it against the last move on the same
+    /// layers. Moves around the same axis commute, so the search looks past them: "R L"
+    /// then "R" is "R2 L", and "M R" then "M'" is "R". The earlier move keeps its
+    /// spelling, so "Rw" then "r" is "Rw2".
+    pub fn push(&mut self, m: Move, inverse: bool) {
+        let moves = if inverse {
+            &mut self.inverse
+        } else {
+            &mut self.normal
+        };
+        for i in (0..moves.len()).rev() {
+            if moves[i].same_layers(&m) {
+                match (moves[i].quarters + m.quarters) % 4 {
+                    0 => {
+                        moves.remove(i);
+                    }
+                    quarters => moves[i].quarters = quarters,
+                }
+                return;
+            }
+            if moves[i].axis() != m.axis() {
+                break;
+            }
+        }
+        moves.push(m);
+    }
+
+    /// Each side undone: its moves reversed and inverted
+    pub fn inverted(&self) -> Notation {
+        let invert = |moves: &Vec<Move>| moves.iter().rev().map(Move::inverted).collect();
+        Notation {
+            normal: invert(&self.normal),
+            inverse: invert(&self.inverse),
+        }
+    }
+
+    /// Every move renamed by `f`, as `Move::relabeled` does
+    pub fn relabeled<F: Fn(char) -> char>(&self, f: F) -> Notation {
+        let map = |moves: &Vec<Move>| moves.iter().map(|m| m.relabeled(&f)).collect();
+        Notation {
+            normal: map(&self.normal),
+            inverse: map(&self.inverse),
+        }
+    }
+
+    /// Reflected through the M, S or E slice, as `mirror` does for face turns
+    pub fn mirrored(&self, plane: &str) -> Result<Notation, String> {
+        let swap = mirror_swap(plane)?;
+        let reverse = |moves: Vec<Move>| moves.iter().map(Move::inverted).collect();
+        let swapped = self.relabeled(swap);
+        Ok(Notation {
+            normal: reverse(swapped.normal),
+            inverse: reverse(swapped.inverse),
+        })
+    }
+
+    pub fn is_face_turns(&self) -> bool {
+        self.normal
+            .iter()
+            .chain(self.inverse.iter())
+            .all(Move::is_face_turn)
+    }
+
+    /// The face turns relative to the centers that leave the same state, as `normalize`
+    /// gives them, with repeated faces cancelled: "M M" is "L2 R2". Face turns alone are
+    /// kept move for move.
+    pub fn expand(&self) -> LibAlgorithm {
+        let turns = |moves: &Vec<Move>| -> Vec<Turn333> {
+            let faces = if moves.iter().all(Move::is_face_turn) {
+                moves.clone()
+            } else {
+                let written: Vec<String> = moves.iter().map(|m| m.to_string()).collect();
+                // Every move is valid notation, so normalizing cannot fail
+                let mut faces = Notation::default();
+                for t in normalize(&written.join(" ")).unwrap().split_whitespace() {
+                    faces.push(Move::from_str(t).unwrap(), false);
+                }
+                faces.normal
+            };
+            faces
+                .iter()
+                .map(|m| Turn333::from_str(&m.to_string()).unwrap())
+                .collect()
+        };
+        LibAlgorithm {
+            normal_moves: turns(&self.normal),
+            inverse_moves: turns(&self.inverse),
+        }
+    }
+}
+
+/// Rewrites a 3x3 scramble that uses wide moves, slice moves or rotations as plain
+/// face turns. Rotations are folded into the view, so the result leaves the same
+/// state relative to the centers.
+pub fn normalize(scramble: &str) -> Result<String, String> {
+    let mut view = Orientation {
+        top: 'U',
+        front: 'F',
+    };
+    let mut turns: Vec<String> = vec![];
+    for token in scramble.split_whitespace() {
+        let Move { layer, quarters } = Move::from_str(token)?;
+        let times = quarters as usize;
+        let (faces, rotation) = match expand_move(layer) {
+            Some((faces, rotation)) => (faces, Some(rotation)),
+            None if "xyz".contains(layer) => ("", layer.chars().next()),
+            None => (layer, None),
+        };
+        for face in faces.split_whitespace() {
+            let mut chars = face.chars();
+            let f = view.absolute(chars.next().unwrap());
+            let dir = if chars.next().is_some() {
+                4 - times
+            } else {
+                times
+            };
+            turns.push(format!("{}{}", f, ["", "", "2", "'"][dir % 4]));
+        }
+        if let Some(r) = rotation {
+            // Upper case marks a counter-clockwise rotation
+            let turns_of_rotation = if r.is_ascii_uppercase() {
+                4 - times
+            } else {
+                times
+            };
+            for _ in 0..turns_of_rotation {
+                view = view.rotated(r.to_ascii_lowercase());
+            }
+        }
+    }
+    let alg = LibAlgorithm::from_str(&turns.join(" "))
+        .map_err(|_| format!("Invalid scramble: {}", scramble))?;
+    Ok(format!("{}", alg))
+}
+
+/// Reflects the algorithm through the M, S or E slice: the two faces parallel to the
+/// slice swap and every turn reverses
+pub fn mirror(alg: &LibAlgorithm, plane: &str) -> Result<LibAlgorithm, String> {
+    let swapped = relabel(alg, mirror_swap(plane)?)?;
+    let reverse = |moves: Vec<Turn333>| moves.into_iter().map(|t| t.invert()).collect();
+    Ok(LibAlgorithm {
+        normal_moves: reverse(swapped.normal_moves),
+        inverse_moves: reverse(swapped.inverse_moves),
+    })
+}
+
+// Swaps the two faces parallel to the M, S or E slice
+fn mirror_swap(plane: &str) -> Result<impl Fn(char) -> char, String> {
+    let (a, b) = match plane.to_uppercase().as_str() {
+        "M" => ('R', 'L'),
+        "S" => ('F', 'B'),
+        "E" => ('U', 'D'),
+        _ => return Err(format!("Invalid plane: {}", plane)),
+    };
+    Ok(move |face| match face {
+        f if f == a => b,
+        f if f == b => a,
+        f => f,
+    })
+}
+
+/// The algorithm as seen from each of the 24 views, with and without mirroring. The
+/// first entry is the algorithm itself.
+pub fn scramble_symmetries(alg: &LibAlgorithm) -> Result<Vec<LibAlgorithm>, String> {
+    let mut symmetries = vec![];
+    for top in "UFRDBL".chars() {
+        for front in "FRUBLD".chars() {
+            let view = match Orientation::from_str(&format!("{}{}", top, front)) {
+                Ok(view) => view,
+                Err(_) => continue,
+            };
+            let rotated = relabel(alg, |face| view.absolute(face))?;
+            let mirrored = mirror(&rotated, "M")?;
+            symmetries.push(rotated);
+            symmetries.push(mirrored);
+        }
+    }
+    Ok(symmetries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_orientation() {
+        let o = Orientation::from_str("uf").unwrap();
+        assert_eq!(o.right(), 'R');
+        let o = Orientation::from_str("ur").unwrap();
+        assert_eq!(o.absolute('F'), 'R');
+        assert_eq!(o.relative('F'), 'L');
+        assert!(Orientation::from_str("ud").is_err());
+        assert!(Orientation::from_str("x").is_err());
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("R U' F2").unwrap(), "R U' F2");
+        assert_eq!(normalize("Rw U").unwrap(), "L F");
+        assert_eq!(normalize("r U").unwrap(), "L F");
+        assert_eq!(normalize("x R y U").unwrap(), "R F");
+        assert_eq!(normalize("M2 U").unwrap(), "L2 R2 D");
+        assert_eq!(normalize("Rw' U").unwrap(), "L' B");
+        assert!(normalize("3Rw U").is_err());
+        assert!(normalize("Q").is_err());
+    }
+
+    #[test]
+    fn test_notation() {
+        let moves = Notation::from_str("M2' R Rw2 (x')").unwrap();
+        assert_eq!(moves.to_string(), "M2 R Rw2 (x')");
+        assert!(!moves.is_face_turns());
+        assert_eq!(Notation::from_str("R L R").unwrap().to_string(), "R2 L");
+        assert_eq!(Notation::from_str("M U M'").unwrap().to_string(), "M U M'");
+        assert_eq!(Notation::from_str("Rw r").unwrap().to_string(), "Rw2");
+        assert_eq!(Notation::from_str("u Uw").unwrap().to_string(), "u2");
+        assert_eq!(
+            Notation::from_str("M2 U").unwrap().expand().to_string(),
+            "L2 R2 D"
+        );
+        assert!(Notation::from_str("3Rw").is_err());
+        assert!(Notation::from_str("R (U").is_err());
+        assert!(Notation::from_str("R U)").is_err());
+    }
+}
@@ -0,0 +1,245 @@
+use crate::finish::FACE_TURNS;
+use cubelib::algs::Algorithm as LibAlgorithm;
+use cubelib::cube::turn::{ApplyAlgorithm, TransformableMut};
+use cubelib::cube::{Cube333, Transformation333};
+use cubelib::steps::coord::Coord;
+use cubelib::steps::finish::coords::HTRFinishCoord;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+const TWISTS: usize = 2187;
+const FLIPS: usize = 2048;
+const SLICES: usize = 495;
+const CORNER_PERMUTATIONS: usize = 40320;
+
+// Each coordinate numbers one projection of the cube: the twist of the corners and
+// the flip of the edges on the UD axis, the positions of the E-slice edges, and the
+// corner permutation. The last corner and edge follow from the others.
+fn twist(cube: &Cube333) -> usize {
+    cube.corners.get_corners()[..7]
+        .iter()
+        .fold(0, |k, c| k * 3 + c.orientation as usize)
+}
+
+fn flip(cube: &Cube333) -> usize {
+    cube.edges.get_edges()[..11]
+        .iter()
+        .fold(0, |k, e| k * 2 + !e.oriented_ud as usize)
+}
+
+// Ranks the set of E-slice edge positions in the combinatorial number system
+fn slice(cube: &Cube333) -> usize {
+    let binomial = |n: usize, k: usize| (0..k).fold(1, |b, i| b * (n - i) / (i + 1));
+    cube.edges
+        .get_edges()
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| (4..8).contains(&e.id))
+        .enumerate()
+        .map(|(k, (pos, _))| binomial(pos, k + 1))
+        .sum()
+}
+
+fn corner_permutation(cube: &Cube333) -> usize {
+    let ids: Vec<u8> = cube.corners.get_corners().iter().map(|c| c.id).collect();
+    (0..8).fold(0, |k, i| {
+        k * (8 - i) + ids[i + 1..].iter().filter(|id| **id < ids[i]).count()
+    })
+}
+
+// For each value of the coordinate, its value after each face turn. A representative
+// cube for every value is found by a breadth-first search from solved.
+fn move_table(size: usize, coord: fn(&Cube333) -> usize) -> Vec<[u16; 18]> {
+    let turns: Vec<LibAlgorithm> = FACE_TURNS
+        .iter()
+        .map(|t| LibAlgorithm::from_str(t).unwrap())
+        .collect();
+    let mut table = vec![[0; 18]; size];
+    let mut seen = vec![false; size];
+    let solved = Cube333::default();
+    seen[coord(&solved)] = true;
+    let mut queue = VecDeque::from([solved]);
+    while let Some(cube) = queue.pop_front() {
+        let from = coord(&cube);
+        for (i, turn) in turns.iter().enumerate() {
+            let mut next = cube.clone();
+            next.apply_alg(turn);
+            let to = coord(&next);
+            table[from][i] = to as u16;
+            if !seen[to] {
+                seen[to] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+    table
+}
+
+// Distance to solved of every pair of values of two coordinates, indexed by
+// `a * b.len() + b`. Pairs that cannot be reached are left at u8::MAX.
+fn pruning_table(a: &[[u16; 18]], a_solved: usize, b: &[[u16; 18]], b_solved: usize) -> Vec<u8> {
+    let mut distances = vec![u8::MAX; a.len() * b.len()];
+    let start = a_solved * b.len() + b_solved;
+    distances[start] = 0;
+    let mut frontier = vec![start];
+    let mut depth = 0;
+    while !frontier.is_empty() {
+        depth += 1;
+        let mut next = vec![];
+        for index in frontier {
+            let (i, j) = (index / b.len(), index % b.len());
+            for turn in 0..18 {
+                let to = a[i][turn] as usize * b.len() + b[j][turn] as usize;
+                if distances[to] == u8::MAX {
+                    distances[to] = depth;
+                    next.push(to);
+                }
+            }
+        }
+        frontier = next;
+    }
+    distances
+}
+
+struct PruningTables {
+    twist_slice: Vec<u8>,
+    flip_slice: Vec<u8>,
+    corners: Vec<u8>,
+}
+
+impl PruningTables {
+    fn new() -> Self {
+        let solved = Cube333::default();
+        let slices = move_table(SLICES, slice);
+        let corners = move_table(CORNER_PERMUTATIONS, corner_permutation);
+        PruningTables {
+            twist_slice: pruning_table(
+                &move_table(TWISTS, twist),
+                twist(&solved),
+                &slices,
+                slice(&solved),
+            ),
+            flip_slice: pruning_table(
+                &move_table(FLIPS, flip),
+                flip(&solved),
+                &slices,
+                slice(&solved),
+            ),
+            corners: pruning_table(&corners, corner_permutation(&solved), &[[0; 18]], 0),
+        }
+    }
+
+    fn lookup(&self, cube: &Cube333) -> usize {
+        let s = slice(cube);
+        [
+            self.twist_slice[twist(cube) * SLICES + s],
+            self.flip_slice[flip(cube) * SLICES + s],
+            self.corners[corner_permutation(cube)],
+        ]
+        .into_iter()
+        .max()
+        .unwrap() as usize
+    }
+}
+
+static PRUNING_TABLES: OnceLock<PruningTables> = OnceLock::new();
+
+/// A lower bound on the length of an optimal solution: the largest exact distance of
+/// the corner twist with the slice edges, the edge flip with the slice edges, and the
+/// corner permutation, taken on each of the three axes. The tables are built on first
+/// use, which takes a moment.
+pub fn lower_bound(cube: &Cube333) -> usize {
+    let tables = PRUNING_TABLES.get_or_init(PruningTables::new);
+    [None, Some(Transformation333::X), Some(Transformation333::Z)]
+        .into_iter()
+        .map(|t| {
+            let mut cube = cube.clone();
+            if let Some(t) = t {
+                cube.transform(t);
+            }
+            tables.lookup(&cube)
+        })
+        .max()
+        .unwrap()
+}
+
+// Distance to solved of every state reachable with half turns, keyed on its finish
+// coordinate, found by a breadth-first search from solved
+fn finish_table() -> HashMap<usize, u8> {
+    let turns: Vec<LibAlgorithm> = FACE_TURNS
+        .iter()
+        .filter(|t| t.ends_with('2'))
+        .map(|t| LibAlgorithm::from_str(t).unwrap())
+        .collect();
+    let solved = Cube333::default();
+    let mut distances = HashMap::from([(HTRFinishCoord::from(&solved).val(), 0)]);
+    let mut frontier = vec![solved];
+    let mut depth = 0;
+    while !frontier.is_empty() {
+        depth += 1;
+        let mut next = vec![];
+        for cube in frontier {
+            for turn in turns.iter() {
+                let mut c = cube.clone();
+                c.apply_alg(turn);
+                if let Entry::Vacant(e) = distances.entry(HTRFinishCoord::from(&c).val()) {
+                    e.insert(depth);
+                    next.push(c);
+                }
+            }
+        }
+        frontier = next;
+    }
+    distances
+}
+
+static FINISH_DISTANCES: OnceLock<HashMap<usize, u8>> = OnceLock::new();
+
+/// The optimal half turn count to solve a cube in HTR, or None if it is not in HTR.
+/// The table of all 663552 HTR states is built on first use.
+pub fn finish_distance(cube: &Cube333) -> Option<usize> {
+    FINISH_DISTANCES
+        .get_or_init(finish_table)
+        .get(&HTRFinishCoord::from(cube).val())
+        .map(|d| *d as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::finish::{distance_within, Finish};
+    use crate::{Cube, Solvable};
+
+    #[test]
+    fn test_coordinates_cover_their_range() {
+        assert_eq!(slice(&Cube333::default()), 4 + 10 + 20 + 35);
+        let tables = PRUNING_TABLES.get_or_init(PruningTables::new);
+        assert!(tables.twist_slice.iter().all(|d| *d != u8::MAX));
+        assert!(tables.flip_slice.iter().all(|d| *d != u8::MAX));
+        assert!(tables.corners.iter().all(|d| *d != u8::MAX));
+    }
+
+    #[test]
+    fn test_lower_bound_never_exceeds_distance() {
+        for alg in ["", "R", "R U", "R U R' U'", "F2 U' R2 D B'"] {
+            let cube = Cube::new(alg.to_string()).unwrap().0;
+            let distance = distance_within(&cube, 5).unwrap();
+            assert!(lower_bound(&cube) <= distance, "{}", alg);
+        }
+        assert_eq!(lower_bound(&Cube333::default()), 0);
+    }
+
+    #[test]
+    fn test_finish_table_covers_htr() {
+        let table = FINISH_DISTANCES.get_or_init(finish_table);
+        assert_eq!(table.len(), 663552);
+        assert_eq!(finish_distance(&Cube333::default()), Some(0));
+        for alg in ["R2", "R2 U2 R2", "R2 U2 F2 L2 D2 B2 U2"] {
+            let cube = Cube::new(alg.to_string()).unwrap().0;
+            let optimal = Finish.solve(&cube, 1).unwrap()[0].len();
+            assert_eq!(finish_distance(&cube), Some(optimal), "{}", alg);
+        }
+    }
+}
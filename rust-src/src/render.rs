@@ -0,0 +1,418 @@
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::finish::{CORNER_NAMES, EDGE_NAMES};
+use crate::Visibility::All;
+use crate::{Cube, CubeView, Solvable};
+use cubelib::cube::{CornerCube333, Cube333, EdgeCube333};
+
+// Same numbering as FaceletColors in palette.py
+const WHITE: u8 = 0;
+const YELLOW: u8 = 1;
+const GREEN: u8 = 2;
+const BLUE: u8 = 3;
+const RED: u8 = 4;
+const ORANGE: u8 = 5;
+
+// Order of faces in the net
+const FACES: [&str; 6] = ["U", "L", "F", "R", "B", "D"];
+const CENTER_COLORS: [u8; 6] = [WHITE, ORANGE, GREEN, RED, BLUE, YELLOW];
+
+// Colors of the corner pieces, for orientation 0,1,2
+const CORNER_PIECE_COLORS: [[u8; 3]; 8] = [
+    [WHITE, ORANGE, BLUE],
+    [WHITE, BLUE, RED],
+    [WHITE, RED, GREEN],
+    [WHITE, GREEN, ORANGE],
+    [YELLOW, ORANGE, GREEN],
+    [YELLOW, GREEN, RED],
+    [YELLOW, RED, BLUE],
+    [YELLOW, BLUE, ORANGE],
+];
+
+// Index of the facelet (orientation 0,1,2) for each of the corners
+const CORNER_POSITION_FACELETS: [[usize; 3]; 8] = [
+    [0, 9, 38],   // UBL
+    [2, 36, 29],  // UBR
+    [8, 27, 20],  // UFR
+    [6, 18, 11],  // UFL
+    [45, 17, 24], // DFL
+    [47, 26, 33], // DFR
+    [53, 35, 42], // DBR
+    [51, 44, 15], // DBL
+];
+
+// Colors of the edge pieces, for orientation 0,1
+const EDGE_PIECE_COLORS: [[u8; 2]; 12] = [
+    [WHITE, BLUE],
+    [WHITE, RED],
+    [WHITE, GREEN],
+    [WHITE, ORANGE],
+    [GREEN, RED],
+    [GREEN, ORANGE],
+    [BLUE, RED],
+    [BLUE, ORANGE],
+    [YELLOW, GREEN],
+    [YELLOW, RED],
+    [YELLOW, BLUE],
+    [YELLOW, ORANGE],
+];
+
+// Index of the facelet (orientation 0,1) for each of the edges
+const EDGE_POSITION_FACELETS: [[usize; 2]; 12] = [
+    [1, 37],  // UB
+    [5, 28],  // UR
+    [7, 19],  // UF
+    [3, 10],  // UL
+    [23, 30], // FR
+    [21, 14], // FL
+    [39, 32], // BR
+    [41, 12], // BL
+    [46, 25], // DF
+    [50, 34], // DR
+    [52, 43], // DB
+    [48, 16], // DL
+];
+
+// The slice where each edge belongs, when the cube is solved
+// 0 = M, 1 = E, 2 = S
+const HOME_SLICE: [u8; 12] = [0, 2, 0, 2, 1, 1, 1, 1, 0, 2, 0, 2];
+
+// Raw orientation bits of a correctly-oriented edge, indexed by the XOR of its home
+// slice and the slice it is in
+const DEFAULT_ORIENTATION: [u8; 4] = [0, 5, 4, 1];
+
+#[derive(Clone)]
+#[pyclass]
+pub struct Sticker {
+    #[pyo3(get)]
+    pub face: String,
+    #[pyo3(get)]
+    pub position: u8,
+    #[pyo3(get)]
+    pub color: u8,
+    #[pyo3(get)]
+    pub visibility: u8,
+}
+
+fn corner_color(side: usize, id: u8, orientation: u8) -> u8 {
+    CORNER_PIECE_COLORS[id as usize][(side + 3 - orientation as usize) % 3]
+}
+
+fn edge_color(pos: usize, side: usize, id: u8, orientation: u8) -> u8 {
+    let home = DEFAULT_ORIENTATION[(HOME_SLICE[id as usize] ^ HOME_SLICE[pos]) as usize];
+    let flipped = if orientation == home { 0 } else { 1 };
+    EDGE_PIECE_COLORS[id as usize][(side + flipped) % 2]
+}
+
+/// All 54 stickers, face by face in U L F R B D order, each face read row by row
+pub fn render_data(cube: &Cube, step: &dyn Solvable) -> PyResult<Vec<Sticker>> {
+    let mut stickers: Vec<Sticker> = (0..54)
+        .map(|i| Sticker {
+            face: FACES[i / 9].to_string(),
+            position: (i % 9) as u8,
+            color: CENTER_COLORS[i / 9],
+            visibility: All as u8,
+        })
+        .collect();
+    let view = CubeView::new(&cube.0);
+    for (pos, (id, orientation)) in cube.corners()?.into_iter().enumerate() {
+        for side in 0..3 {
+            let s = &mut stickers[CORNER_POSITION_FACELETS[pos][side]];
+            s.color = corner_color(side, id, orientation);
+            s.visibility = step.corner_visibility(&view, pos, side as u8);
+        }
+    }
+    for (pos, (id, orientation)) in cube.edges()?.into_iter().enumerate() {
+        for side in 0..2 {
+            let s = &mut stickers[EDGE_POSITION_FACELETS[pos][side]];
+            s.color = edge_color(pos, side, id, orientation);
+            s.visibility = step.edge_visibility(&view, pos, side as u8);
+        }
+    }
+    Ok(stickers)
+}
+
+/// Color of one sticker, given its face and row/column as laid out by `render_data`
+pub fn color_at(cube: &Cube, face: &str, row: usize, col: usize) -> PyResult<u8> {
+    let f = FACES
+        .iter()
+        .position(|f| f.eq_ignore_ascii_case(face))
+        .ok_or_else(|| PyValueError::new_err(format!("Invalid face: {}", face)))?;
+    if row > 2 || col > 2 {
+        return Err(PyValueError::new_err(format!(
+            "Invalid sticker position: {}, {}",
+            row, col
+        )));
+    }
+    let index = 9 * f + 3 * row + col;
+    for (pos, facelets) in CORNER_POSITION_FACELETS.iter().enumerate() {
+        if let Some(side) = facelets.iter().position(|i| *i == index) {
+            let (id, orientation) = cube.corners()?[pos];
+            return Ok(corner_color(side, id, orientation));
+        }
+    }
+    for (pos, facelets) in EDGE_POSITION_FACELETS.iter().enumerate() {
+        if let Some(side) = facelets.iter().position(|i| *i == index) {
+            let (id, orientation) = cube.edges()?[pos];
+            return Ok(edge_color(pos, side, id, orientation));
+        }
+    }
+    Ok(CENTER_COLORS[f])
+}
+
+/// The 54 facelets in the order Kociemba's two-phase solver reads them: faces U R F D
+/// L B, each face row by row, with each sticker named by the face of its color
+pub fn kociemba_string(cube: &Cube) -> PyResult<String> {
+    let mut s = String::new();
+    for face in ["U", "R", "F", "D", "L", "B"] {
+        for row in 0..3 {
+            for col in 0..3 {
+                let color = color_at(cube, face, row, col)?;
+                let f = CENTER_COLORS.iter().position(|c| *c == color).unwrap();
+                s.push_str(FACES[f]);
+            }
+        }
+    }
+    Ok(s)
+}
+
+// Faces in the order Kociemba and most scanners list them
+const KOCIEMBA_FACES: [&str; 6] = ["U", "R", "F", "D", "L", "B"];
+
+/// Reads a facelet string in the format of `kociemba_string`
+#[pyfunction]
+pub fn from_facelets(facelets: &str) -> PyResult<Cube> {
+    let letters: Vec<char> = facelets.trim().to_uppercase().chars().collect();
+    if letters.len() != 54 {
+        return Err(PyValueError::new_err(format!(
+            "Expected 54 facelets, got {}",
+            letters.len()
+        )));
+    }
+    // Colors in net order
+    let mut colors = [0u8; 54];
+    for (i, letter) in letters.iter().enumerate() {
+        let f = FACES
+            .iter()
+            .position(|face| face.starts_with(*letter))
+            .ok_or_else(|| PyValueError::new_err(format!("Invalid facelet: {}", letter)))?;
+        let face = FACES
+            .iter()
+            .position(|face| *face == KOCIEMBA_FACES[i / 9])
+            .unwrap();
+        colors[9 * face + i % 9] = CENTER_COLORS[f];
+    }
+    let solved = Cube333::default();
+    let mut corners = solved.corners.get_corners();
+    for (pos, indices) in CORNER_POSITION_FACELETS.iter().enumerate() {
+        let sticker_colors = indices.map(|i| colors[i]);
+        let (id, orientation) = (0..8)
+            .flat_map(|id| (0..3).map(move |o| (id, o)))
+            .find(|(id, o)| (0..3).all(|s| corner_color(s, *id, *o) == sticker_colors[s]))
+            .ok_or_else(|| {
+                PyValueError::new_err(format!("Impossible colors at corner {}", CORNER_NAMES[pos]))
+            })?;
+        corners[pos].id = id;
+        corners[pos].orientation = orientation;
+    }
+    let mut edges = solved.edges.get_edges();
+    for (pos, indices) in EDGE_POSITION_FACELETS.iter().enumerate() {
+        let sticker_colors = indices.map(|i| colors[i]);
+        let id = (0..12)
+            .find(|id| {
+                let piece = EDGE_PIECE_COLORS[*id as usize];
+                piece.contains(&sticker_colors[0]) && piece.contains(&sticker_colors[1])
+            })
+            .filter(|_| sticker_colors[0] != sticker_colors[1])
+            .ok_or_else(|| {
+                PyValueError::new_err(format!("Impossible colors at edge {}", EDGE_NAMES[pos]))
+            })?;
+        // Raw orientation bits are set for the axes (ud, fb, rl) the edge is bad on
+        let mut bits = DEFAULT_ORIENTATION[(HOME_SLICE[id as usize] ^ HOME_SLICE[pos]) as usize];
+        if sticker_colors[0] != EDGE_PIECE_COLORS[id as usize][0] {
+            bits ^= 7;
+        }
+        edges[pos].id = id;
+        edges[pos].oriented_ud = bits & 4 == 0;
+        edges[pos].oriented_fb = bits & 2 == 0;
+        edges[pos].oriented_rl = bits & 1 == 0;
+    }
+    let cube = Cube(Cube333::new(
+        EdgeCube333::new(edges),
+        CornerCube333::new(corners),
+    ));
+    match cube.validity_error() {
+        Some(e) => Err(PyValueError::new_err(format!("Invalid cube: {}", e))),
+        None => Ok(cube),
+    }
+}
+
+/// Builds a cube from scanner output: six faces of 9 colors each, in U R F D L B
+/// order and read row by row. Colors can be any numbers; each face's center decides
+/// which face its color belongs to.
+#[pyfunction]
+pub fn from_scan(faces: Vec<Vec<u8>>) -> PyResult<Cube> {
+    if faces.len() != 6 {
+        return Err(PyValueError::new_err(format!(
+            "Expected 6 faces, got {}",
+            faces.len()
+        )));
+    }
+    for (f, stickers) in faces.iter().enumerate() {
+        if stickers.len() != 9 {
+            return Err(PyValueError::new_err(format!(
+                "Face {} has {} stickers, expected 9",
+                KOCIEMBA_FACES[f],
+                stickers.len()
+            )));
+        }
+    }
+    let centers: Vec<u8> = faces.iter().map(|stickers| stickers[4]).collect();
+    for f in 0..6 {
+        if let Some(other) = (0..f).find(|other| centers[*other] == centers[f]) {
+            return Err(PyValueError::new_err(format!(
+                "Faces {} and {} have the same center color",
+                KOCIEMBA_FACES[other], KOCIEMBA_FACES[f]
+            )));
+        }
+    }
+    let mut facelets = String::new();
+    for (f, stickers) in faces.iter().enumerate() {
+        for (i, color) in stickers.iter().enumerate() {
+            let face = centers.iter().position(|c| c == color).ok_or_else(|| {
+                PyValueError::new_err(format!(
+                    "Sticker {} on face {} has a color that is not on any center",
+                    i, KOCIEMBA_FACES[f]
+                ))
+            })?;
+            facelets.push_str(KOCIEMBA_FACES[face]);
+        }
+    }
+    for (f, face) in KOCIEMBA_FACES.iter().enumerate() {
+        let count = facelets.matches(face).count();
+        if count != 9 {
+            return Err(PyValueError::new_err(format!(
+                "The color of the {} center appears on {} stickers, expected 9",
+                KOCIEMBA_FACES[f], count
+            )));
+        }
+    }
+    from_facelets(&facelets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StepInfo;
+
+    #[test]
+    fn test_solved_render_data() {
+        let cube = Cube::new("".to_string()).unwrap();
+        let stickers = StepInfo::new("eo", "fb")
+            .unwrap()
+            .render_data(&cube)
+            .unwrap();
+        assert_eq!(stickers.len(), 54);
+        for (i, s) in stickers.iter().enumerate() {
+            assert_eq!(s.face, FACES[i / 9]);
+            assert_eq!(s.color, CENTER_COLORS[i / 9]);
+        }
+    }
+
+    #[test]
+    fn test_color_at() {
+        let cube = Cube::new("".to_string()).unwrap();
+        for (f, face) in FACES.iter().enumerate() {
+            for row in 0..3 {
+                for col in 0..3 {
+                    assert_eq!(color_at(&cube, face, row, col).unwrap(), CENTER_COLORS[f]);
+                }
+            }
+        }
+        assert!(color_at(&cube, "x", 0, 0).is_err());
+        assert!(color_at(&cube, "U", 3, 0).is_err());
+
+        let cube = Cube::new("R U F".to_string()).unwrap();
+        let stickers = StepInfo::new("", "").unwrap().render_data(&cube).unwrap();
+        for (i, s) in stickers.iter().enumerate() {
+            let row = (i % 9) / 3;
+            let col = i % 3;
+            assert_eq!(color_at(&cube, &s.face, row, col).unwrap(), s.color);
+        }
+    }
+
+    #[test]
+    fn test_scrambled_render_data() {
+        let cube = Cube::new("R U F".to_string()).unwrap();
+        let stickers = StepInfo::new("", "").unwrap().render_data(&cube).unwrap();
+        for color in 0..6 {
+            assert_eq!(stickers.iter().filter(|s| s.color == color).count(), 9);
+        }
+    }
+
+    #[test]
+    fn test_from_facelets() {
+        for scramble in ["", "R", "R U R' U'", "R' U' F L2 D B' R2 F' U2 D' R' U' F"] {
+            let cube = Cube::new(scramble.to_string()).unwrap();
+            let read = from_facelets(&kociemba_string(&cube).unwrap()).unwrap();
+            assert_eq!(read.coord_key(), cube.coord_key());
+        }
+        assert!(from_facelets("UUU").is_err());
+        // Two stickers of one corner swapped
+        let twisted = "UUUUUUUURURRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB";
+        assert!(from_facelets(twisted).is_err());
+    }
+
+    fn scan(cube: &Cube) -> Vec<Vec<u8>> {
+        KOCIEMBA_FACES
+            .iter()
+            .map(|face| {
+                (0..9)
+                    .map(|i| color_at(cube, face, i / 3, i % 3).unwrap())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_from_scan() {
+        let cube = Cube::new("R U R' U' F2 D".to_string()).unwrap();
+        let faces = scan(&cube);
+        assert_eq!(
+            from_scan(faces.clone()).unwrap().coord_key(),
+            cube.coord_key()
+        );
+
+        let mut duplicated = faces.clone();
+        duplicated[3][4] = duplicated[0][4];
+        let e = from_scan(duplicated).err().unwrap();
+        assert!(e
+            .to_string()
+            .contains("Faces U and D have the same center color"));
+
+        let mut short = faces.clone();
+        short[2].pop();
+        assert!(from_scan(short).is_err());
+        let mut unknown = faces;
+        unknown[1][0] = 9;
+        assert!(from_scan(unknown).is_err());
+    }
+
+    #[test]
+    fn test_kociemba_string() {
+        let kociemba = |s: &str| kociemba_string(&Cube::new(s.to_string()).unwrap()).unwrap();
+        assert_eq!(
+            kociemba(""),
+            "UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB"
+        );
+        assert_eq!(
+            kociemba("R"),
+            "UUFUUFUUFRRRRRRRRRFFDFFDFFDDDBDDBDDBLLLLLLLLLUBBUBBUBB"
+        );
+        assert_eq!(
+            kociemba("R U R' U'"),
+            "UULUUFUUFRRUBRRURRFFDFFUFFFDDRDDDDDDBLLLLLLLLBRRBBBBBB"
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::{Algorithm, Cube, SolveOptions, StepInfo};
+
+// Possible continuations for each step. The first entry is the default.
+pub fn next_steps(kind: &str, variant: &str) -> Vec<(&'static str, &'static str)> {
+    match (kind, variant) {
+        ("", _) => vec![("eo", "fb"), ("eo", "rl"), ("eo", "ud"), ("finish", "")],
+        ("eo", "ud") => vec![("dr", "fb"), ("dr", "rl"), ("finish", "")],
+        ("eo", "rl") => vec![("dr", "ud"), ("dr", "fb"), ("finish", "")],
+        ("eo", "fb") => vec![("dr", "ud"), ("dr", "rl"), ("finish", "")],
+        ("dr", "ud") => vec![("htr", "ud"), ("finish", "")],
+        ("dr", "rl") => vec![("htr", "rl"), ("finish", "")],
+        ("dr", "fb") => vec![("htr", "fb"), ("finish", "")],
+        ("htr", "ud") => vec![("fr", "ud"), ("finish", ""), ("insertions", "")],
+        ("htr", "rl") => vec![("fr", "rl"), ("finish", ""), ("insertions", "")],
+        ("htr", "fb") => vec![("fr", "fb"), ("finish", ""), ("insertions", "")],
+        ("fr", _) => vec![("finish", ""), ("insertions", "")],
+        ("finish", _) => vec![("insertions", "")],
+        _ => vec![],
+    }
+}
+
+#[derive(Clone)]
+struct SessionStep {
+    info: StepInfo,
+    alg: Algorithm,
+}
+
+/// A solving attempt: the scramble plus the moves applied for each step so far.
+/// The last step is the one being worked on.
+#[pyclass]
+pub struct Session {
+    #[pyo3(get)]
+    scramble: String,
+    steps: Vec<SessionStep>,
+    history: Vec<Vec<SessionStep>>,
+}
+
+impl Session {
+    fn current(&self) -> &SessionStep {
+        self.steps.last().unwrap()
+    }
+
+    fn save_history(&mut self) {
+        self.history.push(self.steps.clone());
+    }
+}
+
+#[pymethods]
+impl Session {
+    #[new]
+    fn new(scramble: &str) -> PyResult<Self> {
+        Cube::new(scramble.to_string())?;
+        Ok(Session {
+            scramble: scramble.to_string(),
+            steps: vec![SessionStep {
+                info: StepInfo::new("", "")?,
+                alg: Algorithm::new("")?,
+            }],
+            history: vec![],
+        })
+    }
+
+    #[getter]
+    fn step(&self) -> StepInfo {
+        self.current().info.clone()
+    }
+
+    #[getter]
+    fn cube(&self) -> PyResult<Cube> {
+        let mut cube = Cube::new(self.scramble.clone())?;
+        cube.apply(&self.full_alg());
+        Ok(cube)
+    }
+
+    fn full_alg(&self) -> Algorithm {
+        self.steps
+            .iter()
+            .fold(Algorithm::new("").unwrap(), |acc, s| acc.merge(&s.alg))
+    }
+
+    #[pyo3(signature = (mv, inverse=false))]
+    fn apply(&mut self, mv: &str, inverse: bool) -> PyResult<()> {
+        let alg = Algorithm::new("")?.append(mv, inverse)?;
+        self.apply_alg(&alg)
+    }
+
+    fn apply_alg(&mut self, alg: &Algorithm) -> PyResult<()> {
+        if self.current().info.kind != "finish" && self.steps.len() > 1 {
+            let previous = &self.steps[self.steps.len() - 2].info;
+            if !previous.are_moves_allowed(alg)? {
+                return Err(PyValueError::new_err(format!(
+                    "Moves not allowed after {}: {}",
+                    previous.kind,
+                    alg.__repr__()
+                )));
+            }
+        }
+        self.save_history();
+        let current = self.steps.last_mut().unwrap();
+        current.alg = current.alg.merge(alg);
+        Ok(())
+    }
+
+    fn solve_current(&self, count: usize) -> PyResult<Vec<Algorithm>> {
+        self.current()
+            .info
+            .solve_with(&self.cube()?, count, &SolveOptions::default())
+    }
+
+    /// Moves on to the next step once the current one is solved. Without arguments,
+    /// picks the default continuation.
+    #[pyo3(signature = (kind=None, variant=None))]
+    fn advance(&mut self, kind: Option<&str>, variant: Option<&str>) -> PyResult<StepInfo> {
+        let current = self.current().info.clone();
+        if !current.is_solved(&self.cube()?)? {
+            return Err(PyValueError::new_err(format!(
+                "{} is not solved",
+                current.kind
+            )));
+        }
+        let choices = next_steps(&current.kind, &current.variant);
+        let next = match kind {
+            None => choices.first(),
+            Some(k) => choices
+                .iter()
+                .find(|(ck, cv)| *ck == k && variant.map_or(true, |v| *cv == v)),
+        };
+        let (kind, variant) = next.ok_or_else(|| {
+            PyValueError::new_err(format!(
+                "Cannot advance from {} to {}",
+                current.kind,
+                kind.unwrap_or("")
+            ))
+        })?;
+        self.save_history();
+        self.steps.push(SessionStep {
+            info: StepInfo::new(kind, variant)?,
+            alg: Algorithm::new("")?,
+        });
+        Ok(self.step())
+    }
+
+    fn undo(&mut self) -> bool {
+        match self.history.pop() {
+            Some(steps) => {
+                self.steps = steps;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn reconstruction(&self) -> String {
+        let mut lines = vec![];
+        let mut total = Algorithm::new("").unwrap();
+        for step in self.steps.iter() {
+            total = total.merge(&step.alg);
+            if step.info.kind.is_empty() && step.alg.is_empty() {
+                continue;
+            }
+            lines.push(format!(
+                "{} // {} ({})",
+                step.alg.__repr__(),
+                step.info.label(),
+                total.len()
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session() {
+        let mut session = Session::new("R' U' F L2 D B' R2 F' U2 D' R' U' F").unwrap();
+        assert!(session.solve_current(1).is_err());
+        for _ in 0..3 {
+            session.advance(None, None).unwrap();
+            let alg = session.solve_current(1).unwrap().remove(0);
+            session.apply_alg(&alg).unwrap();
+        }
+        assert_eq!(session.step().kind, "htr");
+        session.advance(Some("finish"), None).unwrap();
+        let alg = session.solve_current(1).unwrap().remove(0);
+        session.apply_alg(&alg).unwrap();
+        assert!(session.step().is_solved(&session.cube().unwrap()).unwrap());
+        assert_eq!(session.reconstruction().lines().count(), 4);
+
+        assert!(session.undo());
+        assert!(!session.step().is_solved(&session.cube().unwrap()).unwrap());
+        assert!(session.advance(None, None).is_err());
+    }
+
+    #[test]
+    fn test_session_undo() {
+        let mut session = Session::new("R U F").unwrap();
+        session.advance(Some("eo"), Some("fb")).unwrap();
+        session.apply("F", false).unwrap();
+        assert!(session.undo());
+        assert_eq!(session.step().kind, "eo");
+        assert!(session.full_alg().is_empty());
+        assert!(session.undo());
+        assert_eq!(session.step().kind, "");
+        assert!(!session.undo());
+    }
+}
@@ -1,7 +1,7 @@
 use crate::htr::{HTRFB, HTRRL, HTRUD};
-use crate::solver::{solve_step, step_config};
+use crate::solver::{solve_step, step_config, StepFilters};
 use crate::Visibility::{Any, BadFace, BadPiece};
-use crate::{Algorithm, Solvable};
+use crate::{Algorithm, CubeView, Solvable};
 use cubelib::cube::turn::TransformableMut;
 use cubelib::cube::{Cube333, Transformation333};
 use cubelib::defs::{NissSwitchType, StepKind};
@@ -42,31 +42,35 @@ impl Solvable for SliceUD {
         format!("{}c{}e", bad_corner_count, bad_edge_count).to_string()
     }
 
-    fn edge_visibility(&self, _cube: &Cube333, pos: usize, _facelet: u8) -> u8 {
+    fn edge_visibility(&self, _cube: &CubeView, pos: usize, _facelet: u8) -> u8 {
         match pos {
             4 | 5 | 6 | 7 => Any as u8,
             _ => BadPiece as u8 | BadFace as u8,
         }
     }
 
-    fn corner_visibility(&self, _cube: &Cube333, _pos: usize, _facelet: u8) -> u8 {
+    fn corner_visibility(&self, _cube: &CubeView, _pos: usize, _facelet: u8) -> u8 {
         BadFace as u8 | BadPiece as u8
     }
-    fn solve(&self, cube: &Cube333, count: usize) -> PyResult<Vec<Algorithm>> {
+    fn solve_filtered(
+        &self,
+        cube: &Cube333,
+        count: usize,
+        filters: StepFilters,
+    ) -> PyResult<Vec<Algorithm>> {
         solve_step(
             cube,
             step_config(StepKind::FINLS, "ud", NissSwitchType::Never),
             count,
             false,
+            filters,
         )
     }
 }
 pub struct SliceFB;
 impl Solvable for SliceFB {
     fn is_solved(&self, cube: &Cube333) -> bool {
-        let mut cube = cube.clone();
-        cube.transform(Transformation333::X);
-        SliceUD.is_solved(&cube)
+        solved_except_slice(cube, [1, 3, 9, 11])
     }
 
     fn is_eligible(&self, cube: &Cube333) -> bool {
@@ -79,22 +83,28 @@ impl Solvable for SliceFB {
         SliceUD.case_name(&cube)
     }
 
-    fn edge_visibility(&self, _cube: &Cube333, pos: usize, _facelet: u8) -> u8 {
+    fn edge_visibility(&self, _cube: &CubeView, pos: usize, _facelet: u8) -> u8 {
         match pos {
             1 | 3 | 9 | 11 => Any as u8,
             _ => BadPiece as u8 | BadFace as u8,
         }
     }
 
-    fn corner_visibility(&self, _cube: &Cube333, _pos: usize, _facelet: u8) -> u8 {
+    fn corner_visibility(&self, _cube: &CubeView, _pos: usize, _facelet: u8) -> u8 {
         BadPiece as u8 | BadFace as u8
     }
-    fn solve(&self, cube: &Cube333, count: usize) -> PyResult<Vec<Algorithm>> {
+    fn solve_filtered(
+        &self,
+        cube: &Cube333,
+        count: usize,
+        filters: StepFilters,
+    ) -> PyResult<Vec<Algorithm>> {
         solve_step(
             cube,
             step_config(StepKind::FINLS, "fb", NissSwitchType::Never),
             count,
             false,
+            filters,
         )
     }
 }
@@ -102,9 +112,7 @@ impl Solvable for SliceFB {
 pub struct SliceRL;
 impl Solvable for SliceRL {
     fn is_solved(&self, cube: &Cube333) -> bool {
-        let mut cube = cube.clone();
-        cube.transform(Transformation333::Z);
-        SliceUD.is_solved(&cube)
+        solved_except_slice(cube, [0, 2, 8, 10])
     }
 
     fn is_eligible(&self, cube: &Cube333) -> bool {
@@ -117,26 +125,46 @@ impl Solvable for SliceRL {
         SliceUD.case_name(&cube)
     }
 
-    fn edge_visibility(&self, _cube: &Cube333, pos: usize, _facelet: u8) -> u8 {
+    fn edge_visibility(&self, _cube: &CubeView, pos: usize, _facelet: u8) -> u8 {
         match pos {
             0 | 2 | 8 | 10 => Any as u8,
             _ => BadPiece as u8 | BadFace as u8,
         }
     }
 
-    fn corner_visibility(&self, _cube: &Cube333, _pos: usize, _facelet: u8) -> u8 {
+    fn corner_visibility(&self, _cube: &CubeView, _pos: usize, _facelet: u8) -> u8 {
         BadPiece as u8 | BadFace as u8
     }
-    fn solve(&self, cube: &Cube333, count: usize) -> PyResult<Vec<Algorithm>> {
+    fn solve_filtered(
+        &self,
+        cube: &Cube333,
+        count: usize,
+        filters: StepFilters,
+    ) -> PyResult<Vec<Algorithm>> {
         solve_step(
             cube,
             step_config(StepKind::FINLS, "lr", NissSwitchType::Never),
             count,
             false,
+            filters,
         )
     }
 }
 
+// Every piece is solved except for the edges in the given slice positions
+fn solved_except_slice(cube: &Cube333, slice: [usize; 4]) -> bool {
+    let edges = cube.edges.get_edges();
+    let corners = cube.corners.get_corners();
+    corners
+        .iter()
+        .enumerate()
+        .all(|(pos, c)| c.id as usize == pos && c.orientation == 0)
+        && edges
+            .iter()
+            .enumerate()
+            .all(|(pos, e)| slice.contains(&pos) || (e.id as usize == pos && e.oriented_ud))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -149,6 +177,45 @@ mod tests {
         assert!(SliceUD.is_eligible(&cube));
     }
 
+    #[test]
+    fn test_is_solved_matches_transformed() {
+        let algs = [
+            "",
+            "R2",
+            "U2 D2",
+            "F2 B2",
+            "R2 L2",
+            "R L' U2 R' L",
+            "F B' U2 F' B",
+            "U D' F2 U' D",
+            "R2 U2 F2 D2 L2 B2",
+            "R U F",
+        ];
+        let transformed = |cube: &Cube333, t: Transformation333| {
+            let mut cube = cube.clone();
+            cube.transform(t);
+            SliceUD.is_solved(&cube)
+        };
+        let cubes: Vec<Cube333> = algs
+            .iter()
+            .map(|alg| Cube::new(alg.to_string()).unwrap().0)
+            .collect();
+        for (alg, cube) in algs.iter().zip(cubes.iter()) {
+            assert_eq!(
+                SliceFB.is_solved(cube),
+                transformed(cube, Transformation333::X),
+                "{}",
+                alg
+            );
+            assert_eq!(
+                SliceRL.is_solved(cube),
+                transformed(cube, Transformation333::Z),
+                "{}",
+                alg
+            );
+        }
+    }
+
     #[test]
     fn test_solve_slice_ud() {
         let cube = Cube::new("F2 R L' U2 R' L".to_string()).unwrap().0;
@@ -1,10 +1,12 @@
 use std::cell::RefCell;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
+use std::str::FromStr;
+use std::sync::Arc;
 
 use cubelib::algs::Algorithm as LibAlgorithm;
-use cubelib::cube::turn::ApplyAlgorithm;
-use cubelib::cube::Cube333;
+use cubelib::cube::turn::{ApplyAlgorithm, Direction, Invertible, InvertibleMut, TransformableMut};
+use cubelib::cube::{Cube333, Transformation333, Turn333};
 use cubelib::defs::{NissSwitchType, StepKind};
 use cubelib::solver::solution::Solution;
 use cubelib::solver_new::dr::DRBuilder;
@@ -17,13 +19,90 @@ use cubelib::solver_new::util_steps::{FilterFirstN, FilterLastMoveNotPrime};
 use cubelib::steps::step::StepConfig;
 use pyo3::exceptions::PyValueError;
 use pyo3::{pyfunction, PyResult};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
-use crate::Algorithm;
+use crate::finish::FACE_TURNS;
+use crate::orientation::{normalize, scramble_symmetries};
+use crate::{parse_alg, Algorithm, Cube};
 
+/// A random-state scramble. With a `seed`, the cube is drawn from an RNG seeded with
+/// it, and since the solver returns its first solution deterministically, the same
+/// seed gives the same scramble with a given build. The RNG may change with the `rand`
+/// version, so seeded scrambles are not kept across releases. `min_len` and `max_len`
+/// bound the length of the scramble: cubes are drawn until one fits, so tight bounds
+/// take longer, and after 1000 cubes an error is raised.
 #[pyfunction]
-pub fn scramble() -> PyResult<String> {
-    let cube = Cube333::random(&mut rand::rng());
+#[pyo3(signature = (seed=None, min_len=None, max_len=None))]
+pub fn scramble(
+    seed: Option<u64>,
+    min_len: Option<usize>,
+    max_len: Option<usize>,
+) -> PyResult<String> {
+    random_scramble(seed, min_len, max_len).map(|(s, _)| s)
+}
+
+/// Like `scramble`, but also returns the scrambled cube, so it need not be rebuilt
+/// from the string
+#[pyfunction]
+#[pyo3(signature = (seed=None))]
+pub fn scramble_cube(seed: Option<u64>) -> PyResult<(String, Cube)> {
+    random_scramble(seed, None, None).map(|(s, cube)| (s, Cube(cube)))
+}
 
+fn random_scramble(
+    seed: Option<u64>,
+    min_len: Option<usize>,
+    max_len: Option<usize>,
+) -> PyResult<(String, Cube333)> {
+    match seed {
+        Some(seed) => scramble_in_range(&mut StdRng::seed_from_u64(seed), min_len, max_len),
+        None => scramble_in_range(&mut rand::rng(), min_len, max_len),
+    }
+}
+
+// Draws cubes from `rng` until one's pipeline solution has a length in the range
+fn scramble_in_range<R: Rng>(
+    rng: &mut R,
+    min_len: Option<usize>,
+    max_len: Option<usize>,
+) -> PyResult<(String, Cube333)> {
+    let (min_len, max_len) = (min_len.unwrap_or(0), max_len.unwrap_or(usize::MAX));
+    if min_len > max_len {
+        return Err(PyValueError::new_err("min_len is greater than max_len"));
+    }
+    for _ in 0..MAX_SCRAMBLE_ATTEMPTS {
+        let cube = Cube333::random(rng);
+        let alg = solve_pipeline(cube).map_err(|e| PyValueError::new_err(e))?;
+        let len = alg.normal_moves.len();
+        if len >= min_len && len <= max_len {
+            // The scramble is the solution of the random cube, so it reaches the
+            // inverse state
+            let mut scrambled = cube;
+            scrambled.invert();
+            return Ok((format!("{}", alg), scrambled));
+        }
+    }
+    Err(PyValueError::new_err(format!(
+        "No scramble found with length between {} and {} after {} attempts",
+        min_len, max_len, MAX_SCRAMBLE_ATTEMPTS
+    )))
+}
+
+/// A scramble whose generating solution has between `min_len` and `max_len` moves.
+/// Cubes are drawn one after another from a single RNG seeded with `seed` until one
+/// lands in the range, so a given seed and range give the same scramble with a given
+/// build, as with `scramble`.
+#[pyfunction]
+pub fn scramble_seeded_in_range(seed: u64, min_len: usize, max_len: usize) -> PyResult<String> {
+    scramble(Some(seed), Some(min_len), Some(max_len))
+}
+
+const MAX_SCRAMBLE_ATTEMPTS: usize = 1000;
+
+// Solves the cube with a default EO > DR > HTR > finish pipeline. The result is
+// not optimal.
+pub fn solve_pipeline(cube: Cube333) -> Result<LibAlgorithm, String> {
     let eo = EOBuilder::default().build();
     let dr = DRBuilder::default().build();
     let htr = HTRBuilder::default().build();
@@ -35,12 +114,43 @@ pub fn scramble() -> PyResult<String> {
     let solution = steps
         .into_worker(cube)
         .next()
-        .ok_or_else(|| "No solutions found".to_string())
-        .map_err(|e| PyValueError::new_err(e))?;
-    let alg = Into::<LibAlgorithm>::into(solution).to_uninverted();
+        .ok_or_else(|| "No solutions found".to_string())?;
+    Ok(Into::<LibAlgorithm>::into(solution).to_uninverted())
+}
+
+/// The inverse of the scramble as face turns. Rotations and wide or slice moves are
+/// read relative to the centers first, so "x R U" inverts to "F' R'".
+#[pyfunction]
+pub fn invert_scramble(scramble: &str) -> PyResult<String> {
+    let alg = parse_alg(scramble)
+        .ok_or_else(|| PyValueError::new_err(format!("Invalid scramble: {}", scramble)))?;
+    let mut alg = alg.to_uninverted();
+    alg.invert();
     Ok(format!("{}", alg))
 }
 
+/// The representative of the scramble's symmetry class: of all its rotated and
+/// mirrored rewrites, the one leaving the smallest cube state, ties broken by text.
+/// Symmetric scrambles give the same result.
+#[pyfunction]
+pub fn canonical_scramble(scramble: &str) -> PyResult<String> {
+    let alg = parse_alg(scramble)
+        .ok_or_else(|| PyValueError::new_err(format!("Invalid scramble: {}", scramble)))?;
+    let symmetries = scramble_symmetries(&alg).map_err(|e| PyValueError::new_err(e))?;
+    let canonical = symmetries
+        .iter()
+        .map(|alg| (raw(&Cube333::default(), alg), format!("{}", alg)))
+        .min()
+        .unwrap();
+    Ok(canonical.1)
+}
+
+/// The scramble with wide moves, slice moves and rotations rewritten as face turns
+#[pyfunction]
+pub fn normalize_scramble(s: &str) -> PyResult<String> {
+    normalize(s).map_err(|e| PyValueError::new_err(e))
+}
+
 pub fn group(active_step: StepKind, steps_to_solve: &Vec<StepConfig>) -> Result<StepGroup, String> {
     if steps_to_solve.is_empty() {
         return Err("No steps provided".to_string());
@@ -103,6 +213,10 @@ fn single_step(step: &StepConfig) -> Result<StepGroup, String> {
     }
 }
 
+/// Configuration for solving a single step. Every step passes its NISS setting
+/// explicitly: EO uses `Always`, since switching to the inverse is the usual way to
+/// find short EOs; DR, HTR, FR, slice and finish use `Never`, so their solutions stay
+/// on the side the previous step left them.
 pub fn step_config(kind: StepKind, variant: &str, niss: NissSwitchType) -> StepConfig {
     let substeps = match variant {
         "" => None,
@@ -130,13 +244,44 @@ fn raw(cube: &Cube333, alg: &LibAlgorithm) -> [u64; 3] {
     [edges[0], edges[1], corners]
 }
 
+/// Rewrites a solution found on a cube transformed by `t` so that it applies to the
+/// untransformed cube. Each turn becomes the one that `t` carries onto it.
+pub fn transform_solution_frame(alg: &LibAlgorithm, t: Transformation333) -> LibAlgorithm {
+    let turned = |turn: Turn333| {
+        let mut cube = Cube333::default();
+        cube.apply_alg(&LibAlgorithm {
+            normal_moves: vec![turn],
+            inverse_moves: vec![],
+        });
+        cube
+    };
+    let key = |cube: &Cube333| (cube.edges.get_edges_raw(), cube.corners.get_corners_raw());
+    let original = |turn: &Turn333| -> Turn333 {
+        let target = key(&turned(*turn));
+        FACE_TURNS
+            .iter()
+            .map(|s| Turn333::from_str(s).unwrap())
+            .find(|candidate| {
+                let mut cube = turned(*candidate);
+                cube.transform(t);
+                key(&cube) == target
+            })
+            .unwrap()
+    };
+    LibAlgorithm {
+        normal_moves: alg.normal_moves.iter().map(original).collect(),
+        inverse_moves: alg.inverse_moves.iter().map(original).collect(),
+    }
+}
+
 pub fn solve_step(
     cube: &Cube333,
     cfg: StepConfig,
     count: usize,
     require_canonical: bool,
+    filters: StepFilters,
 ) -> PyResult<Vec<Algorithm>> {
-    solve_step_impl(cube, cfg, count, require_canonical, raw)
+    solve_step_impl(cube, cfg, count, require_canonical, raw, filters)
 }
 
 pub fn solve_step_deduplicated<F, T>(
@@ -145,12 +290,13 @@ pub fn solve_step_deduplicated<F, T>(
     n: usize,
     require_canonical: bool,
     case_id: F,
+    filters: StepFilters,
 ) -> PyResult<Vec<Algorithm>>
 where
     F: Fn(&Cube333, &LibAlgorithm) -> T + Sync + Send + 'static,
     T: Eq + std::hash::Hash + Sync + Send + 'static,
 {
-    solve_step_impl(cube, cfg, n, require_canonical, case_id)
+    solve_step_impl(cube, cfg, n, require_canonical, case_id, filters)
 }
 
 fn solve_step_impl<F, T>(
@@ -159,6 +305,7 @@ fn solve_step_impl<F, T>(
     count: usize,
     require_canonical: bool,
     case_id: F,
+    filters: StepFilters,
 ) -> PyResult<Vec<Algorithm>>
 where
     F: Fn(&Cube333, &LibAlgorithm) -> T + Sync + Send + 'static,
@@ -171,15 +318,89 @@ where
         predicates.push(FilterLastMoveNotPrime::new());
     }
     predicates.push(FilterFirstN::new(10000));
-    predicates.push(FilterDupCaseID::new(cube.clone(), case_id));
+    predicates.extend(filters.predicates);
+    let case_id = Arc::new(case_id);
+    if filters.canonical_start {
+        let twin_id = case_id.clone();
+        predicates.push(FilterCanonicalStart::new(
+            cube.clone(),
+            move |c: &Cube333, alg: &LibAlgorithm| twin_id(c, alg),
+        ));
+    }
+    predicates.push(FilterDupCaseID::new(
+        cube.clone(),
+        move |c: &Cube333, alg: &LibAlgorithm| case_id(c, alg),
+    ));
     step_config.with_predicates(predicates);
+    let mut caps = filters.caps;
     Ok(step_config
         .into_worker(cube.clone())
+        .map(Into::<LibAlgorithm>::into)
+        .filter(|alg| within_caps(cube, &mut caps, alg))
         .take(count)
-        .map(|x| Algorithm(x.into()))
+        .map(Algorithm::from)
         .collect())
 }
 
+/// What a step search accepts beyond solving the step. The predicates run before
+/// solutions reaching an already found case are dropped. The caps count the solutions
+/// they accept, so they run after that and only count solutions that are kept.
+/// `canonical_start` drops solutions whose start has a twin reaching the same case.
+#[derive(Default)]
+pub struct StepFilters {
+    pub predicates: Vec<Box<dyn StepPredicate>>,
+    pub caps: Vec<CaseCap>,
+    pub canonical_start: bool,
+}
+
+impl From<Vec<Box<dyn StepPredicate>>> for StepFilters {
+    fn from(predicates: Vec<Box<dyn StepPredicate>>) -> Self {
+        StepFilters {
+            predicates,
+            ..Default::default()
+        }
+    }
+}
+
+/// Accepts at most `limit` solutions for each key, computed from the cube a solution
+/// leads to and the solution itself
+pub struct CaseCap {
+    key: Box<dyn Fn(&Cube333, &LibAlgorithm) -> String>,
+    limit: usize,
+    counts: HashMap<String, usize>,
+}
+
+impl CaseCap {
+    pub fn new<F: Fn(&Cube333, &LibAlgorithm) -> String + 'static>(limit: usize, key: F) -> Self {
+        CaseCap {
+            key: Box::new(key),
+            limit,
+            counts: HashMap::new(),
+        }
+    }
+}
+
+// True if every cap has room for the solution, which is then counted against each
+fn within_caps(cube: &Cube333, caps: &mut [CaseCap], alg: &LibAlgorithm) -> bool {
+    if caps.is_empty() {
+        return true;
+    }
+    let mut c = cube.clone();
+    c.apply_alg(alg);
+    let keys: Vec<String> = caps.iter().map(|cap| (cap.key)(&c, alg)).collect();
+    let full = caps
+        .iter()
+        .zip(keys.iter())
+        .any(|(cap, key)| cap.counts.get(key).map_or(false, |n| *n >= cap.limit));
+    if full {
+        return false;
+    }
+    for (cap, key) in caps.iter_mut().zip(keys) {
+        *cap.counts.entry(key).or_insert(0) += 1;
+    }
+    true
+}
+
 struct FilterDupCaseID<
     F: Fn(&Cube333, &LibAlgorithm) -> T + Sync + Send,
     T: Eq + Hash + Sync + Send,
@@ -211,6 +432,177 @@ impl<F: Fn(&Cube333, &LibAlgorithm) -> T + Sync + Send, T: Eq + Hash + Sync + Se
     }
 }
 
+/// Keeps one solution from each set of solutions that are rotations or mirrors of
+/// each other. Counted after the case dedup, so a dropped duplicate never takes a class.
+pub fn symmetry_dedup_cap() -> CaseCap {
+    CaseCap::new(1, |_c: &Cube333, alg: &LibAlgorithm| {
+        scramble_symmetries(alg)
+            .unwrap_or_default()
+            .iter()
+            .map(|a| format!("{}", a))
+            .min()
+            .unwrap_or_default()
+    })
+}
+
+// Accepts only solutions whose resulting cube satisfies the given check
+pub struct FilterResultState<P: Fn(&Cube333) -> bool + Sync + Send>(Cube333, P);
+
+impl<P: Fn(&Cube333) -> bool + Sync + Send + 'static> FilterResultState<P> {
+    pub fn new(cube: Cube333, accept: P) -> Box<dyn StepPredicate> {
+        Box::new(Self(cube, accept))
+    }
+}
+
+impl<P: Fn(&Cube333) -> bool + Sync + Send> StepPredicate for FilterResultState<P> {
+    fn check_solution(&self, solution: &Solution) -> StepPredicateResult {
+        let alg: LibAlgorithm = solution.clone().into();
+        let mut c = self.0.clone();
+        c.apply_alg(&alg);
+        if self.1(&c) {
+            StepPredicateResult::Accepted
+        } else {
+            StepPredicateResult::Rejected
+        }
+    }
+}
+
+// Accepts only solutions where every state along the way satisfies the given check
+pub struct FilterEveryState<P: Fn(&Cube333) -> bool + Sync + Send>(Cube333, P);
+
+impl<P: Fn(&Cube333) -> bool + Sync + Send + 'static> FilterEveryState<P> {
+    pub fn new(cube: Cube333, accept: P) -> Box<dyn StepPredicate> {
+        Box::new(Self(cube, accept))
+    }
+}
+
+impl<P: Fn(&Cube333) -> bool + Sync + Send> StepPredicate for FilterEveryState<P> {
+    fn check_solution(&self, solution: &Solution) -> StepPredicateResult {
+        let alg: LibAlgorithm = solution.clone().into();
+        if states_along(&self.0, &alg).iter().all(|c| self.1(c)) {
+            StepPredicateResult::Accepted
+        } else {
+            StepPredicateResult::Rejected
+        }
+    }
+}
+
+/// The cube before each move of the algorithm and after the last one. Inverse moves
+/// are applied on the inverse, after the normal ones.
+pub fn states_along(cube: &Cube333, alg: &LibAlgorithm) -> Vec<Cube333> {
+    let single = |t: &Turn333| LibAlgorithm {
+        normal_moves: vec![*t],
+        inverse_moves: vec![],
+    };
+    let mut c = cube.clone();
+    let mut states = vec![c.clone()];
+    for t in alg.normal_moves.iter() {
+        c.apply_alg(&single(t));
+        states.push(c.clone());
+    }
+    for t in alg.inverse_moves.iter() {
+        c.invert();
+        c.apply_alg(&single(t));
+        c.invert();
+        states.push(c.clone());
+    }
+    states
+}
+
+// Accepts only solutions whose last move satisfies the given check. On NISS
+// solutions, the last normal move is checked, or the last inverse move if there are
+// no normal moves.
+pub struct FilterLastMove<P: Fn(&Turn333) -> bool + Sync + Send>(P);
+
+impl<P: Fn(&Turn333) -> bool + Sync + Send + 'static> FilterLastMove<P> {
+    pub fn new(accept: P) -> Box<dyn StepPredicate> {
+        Box::new(Self(accept))
+    }
+}
+
+impl<P: Fn(&Turn333) -> bool + Sync + Send> StepPredicate for FilterLastMove<P> {
+    fn check_solution(&self, solution: &Solution) -> StepPredicateResult {
+        let alg: LibAlgorithm = solution.clone().into();
+        let last = alg.normal_moves.last().or_else(|| alg.inverse_moves.last());
+        match last {
+            Some(t) if self.0(t) => StepPredicateResult::Accepted,
+            _ => StepPredicateResult::Rejected,
+        }
+    }
+}
+
+// Rejects solutions starting with a counter-clockwise quarter turn when the same
+// solution starting with the clockwise turn reaches the same case. The start is the
+// first normal move, or with no normal moves, the last inverse move, which is the
+// first move seen from the normal side.
+struct FilterCanonicalStart<
+    F: Fn(&Cube333, &LibAlgorithm) -> T + Sync + Send,
+    T: Eq + Hash + Sync + Send,
+>(Cube333, F);
+
+impl<
+        F: Fn(&Cube333, &LibAlgorithm) -> T + Sync + Send + 'static,
+        T: Eq + Hash + Sync + Send + 'static,
+    > FilterCanonicalStart<F, T>
+{
+    pub fn new(cube: Cube333, case_id_fn: F) -> Box<dyn StepPredicate> {
+        Box::new(Self(cube, case_id_fn))
+    }
+
+    fn case_id(&self, alg: &LibAlgorithm) -> T {
+        let mut c = self.0.clone();
+        c.apply_alg(alg);
+        self.1(&c, alg)
+    }
+}
+
+impl<
+        F: Fn(&Cube333, &LibAlgorithm) -> T + Sync + Send + 'static,
+        T: Eq + Hash + Sync + Send + 'static,
+    > StepPredicate for FilterCanonicalStart<F, T>
+{
+    fn check_solution(&self, solution: &Solution) -> StepPredicateResult {
+        let alg: LibAlgorithm = solution.clone().into();
+        let mut twin = alg.clone();
+        let prime_start = if let Some(t) = twin.normal_moves.first_mut() {
+            let prime = t.dir == Direction::CounterClockwise;
+            *t = t.invert();
+            prime
+        } else if let Some(t) = twin.inverse_moves.last_mut() {
+            let prime = t.dir == Direction::Clockwise;
+            *t = t.invert();
+            prime
+        } else {
+            false
+        };
+        if prime_start && self.case_id(&twin) == self.case_id(&alg) {
+            StepPredicateResult::Rejected
+        } else {
+            StepPredicateResult::Accepted
+        }
+    }
+}
+
+// Rejects solutions that use NISS
+pub struct FilterLinear;
+
+impl FilterLinear {
+    pub fn new() -> Box<dyn StepPredicate> {
+        Box::new(Self)
+    }
+}
+
+impl StepPredicate for FilterLinear {
+    fn check_solution(&self, solution: &Solution) -> StepPredicateResult {
+        let alg: LibAlgorithm = solution.clone().into();
+        if alg.inverse_moves.is_empty() {
+            StepPredicateResult::Accepted
+        } else {
+            StepPredicateResult::Rejected
+        }
+    }
+}
+
 pub fn parse_steps(steps_str: &str) -> Result<Vec<StepConfig>, String> {
     let parts: Vec<&str> = steps_str.split(" > ").map(|s| s.trim()).collect();
     let mut steps = Vec::new();
@@ -292,7 +684,13 @@ pub fn parse_single_step(step_str: &str) -> Result<StepConfig, String> {
         substeps: params
             .get("variant")
             .and_then(|s| Some(s.split(',').map(|s| s.trim().to_string()).collect())),
-        min: None,
+        min: params
+            .get("min")
+            .map(|s| {
+                s.parse::<u8>()
+                    .map_err(|_| format!("Invalid value min={}", s))
+            })
+            .transpose()?,
         max: params
             .get("max")
             .map(|s| {
@@ -300,7 +698,13 @@ pub fn parse_single_step(step_str: &str) -> Result<StepConfig, String> {
                     .map_err(|_| format!("Invalid value max={}", s))
             })
             .transpose()?,
-        absolute_min: None,
+        absolute_min: params
+            .get("abs-min")
+            .map(|s| {
+                s.parse::<u8>()
+                    .map_err(|_| format!("Invalid value abs-min={}", s))
+            })
+            .transpose()?,
         absolute_max: params
             .get("abs-max")
             .map(|s| {
@@ -315,9 +719,114 @@ pub fn parse_single_step(step_str: &str) -> Result<StepConfig, String> {
                     .map_err(|_| format!("Invalid value value limit={}", s))
             })
             .transpose()?,
-        quality: 0,
+        quality: params
+            .get("quality")
+            .map(|s| {
+                s.parse::<usize>()
+                    .map_err(|_| format!("Invalid value quality={}", s))
+            })
+            .transpose()?
+            .unwrap_or(0),
         niss: niss_type,
         params: Default::default(),
     };
     Ok(step)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orientation::mirror;
+
+    #[test]
+    fn test_parse_min() {
+        let step = parse_single_step("DR[ud;min=4;max=9]").unwrap();
+        assert_eq!(step.min, Some(4));
+        assert_eq!(step.max, Some(9));
+        assert_eq!(parse_single_step("DR[ud]").unwrap().min, None);
+        assert_eq!(
+            parse_single_step("EO[min=x]").err(),
+            Some("Invalid value min=x".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_abs_min_and_quality() {
+        let step = parse_single_step("EO[ud;abs-min=2;abs-max=6;quality=500]").unwrap();
+        assert_eq!(step.absolute_min, Some(2));
+        assert_eq!(step.absolute_max, Some(6));
+        assert_eq!(step.quality, 500);
+        let step = parse_single_step("EO[ud]").unwrap();
+        assert_eq!(step.absolute_min, None);
+        assert_eq!(step.quality, 0);
+        assert_eq!(
+            parse_single_step("EO[abs-min=-1]").err(),
+            Some("Invalid value abs-min=-1".to_string())
+        );
+        assert_eq!(
+            parse_single_step("EO[quality=high]").err(),
+            Some("Invalid value quality=high".to_string())
+        );
+    }
+
+    #[test]
+    fn test_seeded_scramble_in_range() {
+        let s = scramble_seeded_in_range(42, 0, 100).unwrap();
+        let len = LibAlgorithm::from_str(&s).unwrap().normal_moves.len();
+        assert_eq!(scramble_seeded_in_range(42, len, len).unwrap(), s);
+        assert_eq!(scramble_seeded_in_range(42, 0, 100).unwrap(), s);
+        assert!(scramble_seeded_in_range(42, 10, 5).is_err());
+    }
+
+    #[test]
+    fn test_seeded_scramble() {
+        let s = scramble(Some(42), None, None).unwrap();
+        assert_eq!(scramble(Some(42), None, None).unwrap(), s);
+        assert_ne!(scramble(Some(43), None, None).unwrap(), s);
+        // The first cube drawn from the seed, so any range accepts it
+        assert_eq!(scramble_seeded_in_range(42, 0, 100).unwrap(), s);
+    }
+
+    #[test]
+    fn test_scramble_length_bounds() {
+        let length = |s: &str| LibAlgorithm::from_str(s).unwrap().normal_moves.len();
+        let typical = length(&scramble(Some(42), None, None).unwrap());
+        let (min_len, max_len) = (typical.saturating_sub(2), typical + 2);
+        let s = scramble(Some(7), Some(min_len), Some(max_len)).unwrap();
+        assert!((min_len..=max_len).contains(&length(&s)));
+        assert!(scramble(None, Some(10), Some(5)).is_err());
+    }
+
+    #[test]
+    fn test_scramble_cube() {
+        let (s, mut cube) = scramble_cube(Some(42)).unwrap();
+        assert_eq!(s, scramble(Some(42), None, None).unwrap());
+        assert_eq!(cube.coord_key(), Cube::new(s.clone()).unwrap().coord_key());
+        cube.apply(&Algorithm::new(&s).unwrap().inverted());
+        assert_eq!(
+            cube.coord_key(),
+            Cube::new("".to_string()).unwrap().coord_key()
+        );
+    }
+
+    #[test]
+    fn test_canonical_scramble() {
+        let scramble = "R U F D2 L' B";
+        let canonical = canonical_scramble(scramble).unwrap();
+        let alg = LibAlgorithm::from_str(scramble).unwrap();
+        let symmetries = scramble_symmetries(&alg).unwrap();
+        assert_eq!(symmetries.len(), 48);
+        for sym in symmetries {
+            assert_eq!(canonical_scramble(&format!("{}", sym)).unwrap(), canonical);
+        }
+        let mirrored = format!("{}", mirror(&alg, "M").unwrap());
+        assert_eq!(mirrored, "L' U' F' D2 R B'");
+        assert_eq!(canonical_scramble(&mirrored).unwrap(), canonical);
+        assert_ne!(canonical_scramble("R U F D2 L' B'").unwrap(), canonical);
+        assert_eq!(
+            canonical_scramble("Rw U M2").unwrap(),
+            canonical_scramble(&normalize("Rw U M2").unwrap()).unwrap()
+        );
+        assert!(canonical_scramble("R Q").is_err());
+    }
+}